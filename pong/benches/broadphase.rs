@@ -0,0 +1,86 @@
+use bevy::math::Vec2;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[path = "../src/broadphase.rs"]
+mod broadphase;
+
+use broadphase::SpatialGrid;
+
+const ARENA_SIZE: f32 = 4000.;
+const BALL_RADIUS: f32 = 5.;
+const CELL_SIZE: f32 = 64.;
+
+// Prosty generator liniowy, żeby pozycje były powtarzalne między przebiegami
+fn positions(count: usize) -> Vec<Vec2> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 40) as f32 / (1u64 << 24) as f32 * ARENA_SIZE - ARENA_SIZE / 2.
+    };
+    (0..count).map(|_| Vec2::new(next(), next())).collect()
+}
+
+fn overlaps(a: Vec2, b: Vec2) -> bool {
+    a.distance_squared(b) <= (2. * BALL_RADIUS) * (2. * BALL_RADIUS)
+}
+
+fn brute_force_pairs(balls: &[Vec2]) -> usize {
+    let mut pairs = 0;
+    for (i, &a) in balls.iter().enumerate() {
+        for &b in &balls[i + 1..] {
+            if overlaps(a, b) {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+fn grid_pairs(grid: &mut SpatialGrid<usize>, balls: &[Vec2], candidates: &mut Vec<usize>) -> usize {
+    let half = Vec2::splat(BALL_RADIUS);
+    grid.clear();
+    for (i, &ball) in balls.iter().enumerate() {
+        grid.insert(i, ball - half, ball + half);
+    }
+
+    let mut pairs = 0;
+    for (i, &a) in balls.iter().enumerate() {
+        candidates.clear();
+        grid.query(a - half, a + half, candidates);
+        for &j in candidates.iter() {
+            if j > i && overlaps(a, balls[j]) {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+fn broadphase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ball_pairs");
+    for count in [100, 1000, 5000, 10000] {
+        let balls = positions(count);
+        assert_eq!(
+            brute_force_pairs(&balls),
+            grid_pairs(&mut SpatialGrid::new(CELL_SIZE), &balls, &mut Vec::new())
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("brute_force", count),
+            &balls,
+            |b, balls| b.iter(|| brute_force_pairs(black_box(balls))),
+        );
+
+        let mut grid = SpatialGrid::new(CELL_SIZE);
+        let mut candidates = Vec::new();
+        group.bench_with_input(BenchmarkId::new("grid", count), &balls, |b, balls| {
+            b.iter(|| grid_pairs(&mut grid, black_box(balls), &mut candidates))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
@@ -0,0 +1,189 @@
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::player::AiDifficulty;
+
+const PROFILES_FILE: &str = "ai_profiles.json";
+const BUNDLED_PROFILES: &str = include_str!("../assets/ai_profiles.json");
+const PERCEPTION_HISTORY_SECONDS: f32 = 2.;
+pub const MAX_REACTION_DELAY: f32 = 1.;
+
+// Parametry "ludzkiego" przeciwnika, wczytywane z assets/ai_profiles.json
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AiProfile {
+    pub reaction_delay: f32,
+    pub deadzone: f32,
+    pub noise_base: f32,
+    pub noise_per_speed: f32,
+    pub edge_hit_chance: f32,
+    pub edge_offset: f32,
+    pub fatigue_per_hit: f32,
+    pub max_fatigue: f32,
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct AiProfiles {
+    pub easy: AiProfile,
+    pub normal: AiProfile,
+    pub hard: AiProfile,
+}
+
+// Jedynym źródłem wartości jest plik w assets; wersja wkompilowana w grę
+// zastępuje go, gdy pliku nie ma obok gry
+impl Default for AiProfiles {
+    fn default() -> Self {
+        serde_json::from_str(BUNDLED_PROFILES).expect("bundled ai_profiles.json is invalid")
+    }
+}
+
+impl AiProfiles {
+    pub fn path() -> PathBuf {
+        FileAssetIo::get_base_path()
+            .join("assets")
+            .join(PROFILES_FILE)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, difficulty: AiDifficulty) -> &AiProfile {
+        match difficulty {
+            AiDifficulty::Easy => &self.easy,
+            AiDifficulty::Normal => &self.normal,
+            AiDifficulty::Hard => &self.hard,
+        }
+    }
+
+    pub fn get_mut(&mut self, difficulty: AiDifficulty) -> &mut AiProfile {
+        match difficulty {
+            AiDifficulty::Easy => &mut self.easy,
+            AiDifficulty::Normal => &mut self.normal,
+            AiDifficulty::Hard => &mut self.hard,
+        }
+    }
+}
+
+// Stan jednej paletki AI: błąd celowania losowany raz na odbicie i zmęczenie w wymianie
+#[derive(Component)]
+pub struct AiMind {
+    rng: u64,
+    pub error: f32,
+    pub edge: f32,
+    pub fatigue: f32,
+}
+
+impl AiMind {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: seed,
+            error: 0.,
+            edge: 0.,
+            fatigue: 0.,
+        }
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.rng = self
+            .rng
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Suma trzech rozkładów jednostajnych wystarczająco przypomina rozkład normalny
+    fn next_noise(&mut self) -> f32 {
+        (self.next_f32() + self.next_f32() + self.next_f32() - 1.5) * 2.
+    }
+
+    pub fn rest(&mut self) {
+        self.fatigue = 0.;
+    }
+
+    pub fn tire(&mut self, profile: &AiProfile) {
+        self.fatigue = (self.fatigue + profile.fatigue_per_hit).min(profile.max_fatigue);
+    }
+
+    pub fn reaction_delay(&self, profile: &AiProfile) -> f32 {
+        profile.reaction_delay * (1. + self.fatigue)
+    }
+
+    pub fn rethink(&mut self, profile: &AiProfile, ball_speed: f32, half_paddle: f32) {
+        let spread =
+            (profile.noise_base + profile.noise_per_speed * ball_speed) * (1. + self.fatigue);
+        self.error = spread * self.next_noise();
+        self.edge = if self.next_f32() < profile.edge_hit_chance {
+            let side = if self.next_f32() < 0.5 { -1. } else { 1. };
+            side * profile.edge_offset * half_paddle
+        } else {
+            0.
+        };
+    }
+}
+
+pub struct PerceivedBall {
+    pub position: Vec3,
+    pub direction: Vec2,
+    pub speed: f32,
+}
+
+// Historia stanów piłek, z której AI "widzi" świat z opóźnieniem
+#[derive(Default)]
+pub struct Perception {
+    clock: f32,
+    history: VecDeque<(f32, Vec<PerceivedBall>)>,
+}
+
+impl Perception {
+    pub fn record(&mut self, delta: f32, balls: Vec<PerceivedBall>) {
+        self.clock += delta;
+        self.history.push_back((self.clock, balls));
+        while self.history.len() > 1 && self.history[1].0 <= self.clock - PERCEPTION_HISTORY_SECONDS
+        {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn sample(&self, delay: f32) -> &[PerceivedBall] {
+        let seen_at = self.clock - delay;
+        self.history
+            .iter()
+            .rev()
+            .find(|(time, _)| *time <= seen_at)
+            .or_else(|| self.history.front())
+            .map(|(_, balls)| balls.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+pub fn load_ai_profiles_system(mut commands: Commands) {
+    let path = AiProfiles::path();
+    if !path.exists() {
+        return;
+    }
+    match AiProfiles::load(&path) {
+        Ok(profiles) => commands.insert_resource(profiles),
+        Err(err) => error!("Failed to load AI profiles from {}: {err}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_profiles_parse() {
+        let profiles = AiProfiles::default();
+        assert!(profiles.easy.reaction_delay > profiles.hard.reaction_delay);
+    }
+}
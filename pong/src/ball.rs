@@ -1,77 +1,227 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{collisions::BallCollider, GameState, Score, Velocity};
+use crate::{
+    collisions::{BallCollider, BallHitEvent, CollisionPhase},
+    player::Side,
+    time_control::simulation_run_criteria,
+    GameState, MatchEntity, MatchRules, Score, Velocity,
+};
 
 const BALL_DIAMETER: f32 = 10.0;
-const BALL_SPEED: f32 = 500.0;
+pub const BALL_SPEED: f32 = 500.0;
+pub const MAX_BALL_SPEED: f32 = 2000.0;
 
 pub struct BallPlugin;
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct Ball {
     active: bool,
+    #[serde(default)]
+    rally: u32,
 }
 
-fn setup_ball(mut commands: Commands) {
-    commands.spawn((
-        Ball { active: true },
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::rgb(0.8, 0.8, 1.0),
-                custom_size: Some(Vec2::new(BALL_DIAMETER, BALL_DIAMETER)),
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpeedProfile {
+    Linear { per_hit: f32 },
+    Stepped { per_step: f32, hits_per_step: u32 },
+    Exponential { factor: f32 },
+}
+
+impl SpeedProfile {
+    pub const ALL: [SpeedProfile; 3] = [
+        SpeedProfile::Linear { per_hit: 40. },
+        SpeedProfile::Stepped {
+            per_step: 150.,
+            hits_per_step: 4,
+        },
+        SpeedProfile::Exponential { factor: 1.1 },
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpeedProfile::Linear { .. } => "Linear",
+            SpeedProfile::Stepped { .. } => "Stepped",
+            SpeedProfile::Exponential { .. } => "Exponential",
+        }
+    }
+}
+
+// Prędkość piłki zależy tylko od liczby odbić w wymianie, więc serwis zawsze zaczyna od bazowej
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpeedCurve {
+    pub base_speed: f32,
+    pub max_speed: f32,
+    pub profile: SpeedProfile,
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Self {
+            base_speed: BALL_SPEED,
+            max_speed: MAX_BALL_SPEED,
+            profile: SpeedProfile::Exponential { factor: 1.1 },
+        }
+    }
+}
+
+impl SpeedCurve {
+    pub fn speed_for_rally(&self, hits: u32) -> f32 {
+        let speed = match self.profile {
+            SpeedProfile::Linear { per_hit } => self.base_speed + per_hit * hits as f32,
+            SpeedProfile::Stepped {
+                per_step,
+                hits_per_step,
+            } => self.base_speed + per_step * (hits / hits_per_step.max(1)) as f32,
+            SpeedProfile::Exponential { factor } => {
+                self.base_speed * factor.powi(hits.min(i32::MAX as u32) as i32)
+            }
+        };
+        speed.min(self.max_speed)
+    }
+}
+
+pub struct GoalEvent {
+    pub ball: Entity,
+    pub scoring_side: Side,
+}
+
+pub struct ServeEvent {
+    pub ball: Entity,
+}
+
+// Serwuje strona, która zdobyła ostatni punkt - piłka po resecie leci od niej
+#[derive(Resource)]
+pub struct NextServe(pub Side);
+
+impl Default for NextServe {
+    fn default() -> Self {
+        NextServe(Side::Left)
+    }
+}
+
+fn setup_ball(mut commands: Commands, rules: Res<MatchRules>) {
+    commands.insert_resource(NextServe::default());
+    spawn_ball(
+        &mut commands,
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec2::new(1.0, 1.0).normalize(),
+        rules.speed.base_speed,
+    );
+}
+
+pub fn spawn_ball(
+    commands: &mut Commands,
+    translation: Vec3,
+    direction: Vec2,
+    speed: f32,
+) -> Entity {
+    commands
+        .spawn((
+            MatchEntity,
+            Ball {
+                active: true,
+                rally: 0,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.8, 0.8, 1.0),
+                    custom_size: Some(Vec2::new(BALL_DIAMETER, BALL_DIAMETER)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
                 ..default()
             },
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-            ..default()
-        },
-        BallCollider {
-            radius: BALL_DIAMETER / 2.,
-        },
-        Velocity {
-            direction: Vec2::new(1.0, 1.0).normalize(),
-            speed: BALL_SPEED,
-        },
-    ));
+            BallCollider {
+                radius: BALL_DIAMETER / 2.,
+            },
+            Velocity { direction, speed },
+        ))
+        .id()
 }
 
 fn check_for_ball_score(
     mut score_query: Query<&mut Score>,
-    mut ball_query: Query<(&Transform, &mut Ball)>,
+    mut ball_query: Query<(Entity, &Transform, &mut Ball)>,
+    mut goal_events: EventWriter<GoalEvent>,
+    mut next_serve: ResMut<NextServe>,
     windows: Res<Windows>,
 ) {
     let window = windows.get_primary().unwrap();
 
-    let mut score = score_query.get_single_mut().unwrap();
-    for (ball_trans, mut ball) in ball_query.iter_mut() {
+    let Ok(mut score) = score_query.get_single_mut() else {
+        return;
+    };
+    for (entity, ball_trans, mut ball) in ball_query.iter_mut() {
         if ball.active {
             if ball_trans.translation.x > window.width() / 2. {
                 score.player1_score += 1;
                 ball.active = false;
+                next_serve.0 = Side::Left;
+                goal_events.send(GoalEvent {
+                    ball: entity,
+                    scoring_side: Side::Left,
+                });
             } else if ball_trans.translation.x < -window.width() / 2. {
                 score.player2_score += 1;
                 ball.active = false;
+                next_serve.0 = Side::Right;
+                goal_events.send(GoalEvent {
+                    ball: entity,
+                    scoring_side: Side::Right,
+                });
             }
         }
     }
 }
 
-fn ball_reset_system(keys: Res<Input<KeyCode>>, mut query: Query<(&mut Transform, &mut Ball, &mut Velocity)>) {
+fn rally_speed_system(
+    mut hit_events: EventReader<BallHitEvent>,
+    mut ball_query: Query<(&mut Ball, &mut Velocity)>,
+    rules: Res<MatchRules>,
+) {
+    for hit in hit_events.iter() {
+        if let Ok((mut ball, mut velocity)) = ball_query.get_mut(hit.ball) {
+            ball.rally += 1;
+            velocity.speed = rules.speed.speed_for_rally(ball.rally);
+        }
+    }
+}
+
+fn ball_reset_system(
+    keys: Res<Input<KeyCode>>,
+    rules: Res<MatchRules>,
+    next_serve: Res<NextServe>,
+    mut query: Query<(Entity, &mut Transform, &mut Ball, &mut Velocity)>,
+    mut serve_events: EventWriter<ServeEvent>,
+) {
     if keys.just_pressed(KeyCode::R) {
-        for (mut transform, mut ball, mut vel) in query.iter_mut() {
+        for (entity, mut transform, mut ball, mut vel) in query.iter_mut() {
             transform.translation = Vec3::new(0., 0., 0.);
             ball.active = true;
-            vel.speed = BALL_SPEED;
+            ball.rally = 0;
+            vel.speed = rules.speed.base_speed;
+            vel.direction.x = match next_serve.0 {
+                Side::Left => vel.direction.x.abs(),
+                Side::Right => -vel.direction.x.abs(),
+            };
+            serve_events.send(ServeEvent { ball: entity });
         }
     }
 }
 
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_ball)
+        app.add_event::<GoalEvent>()
+            .init_resource::<NextServe>()
+            .add_event::<ServeEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_ball))
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(ball_reset_system))
             .add_system_set(
-                SystemSet::on_update(GameState::InGame)
-                    .with_system(ball_reset_system)
-                    .with_system(check_for_ball_score),
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
+                    .with_system(check_for_ball_score)
+                    .with_system(rally_speed_system.after(CollisionPhase)),
             );
     }
 }
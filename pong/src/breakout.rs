@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    collisions::{ObstacleHitEvent, PlayerCollider},
+    GameMode, GameState, MatchEntity, MatchSetup, Score,
+};
+
+const BRICK_WIDTH: f32 = 20.;
+const BRICK_HEIGHT: f32 = 50.;
+const BRICK_GAP: f32 = 10.;
+const BRICK_COLUMNS: usize = 4;
+const BRICKS_FROM_EDGE_MARGIN: f32 = 60.;
+
+pub struct BreakoutPlugin;
+
+#[derive(Component)]
+pub struct Brick;
+
+fn setup_bricks(mut commands: Commands, windows: Res<Windows>, setup: Res<MatchSetup>) {
+    if setup.mode != GameMode::Breakout {
+        return;
+    }
+    let window = windows.get_primary().unwrap();
+
+    let rows = ((window.height() - BRICK_GAP) / (BRICK_HEIGHT + BRICK_GAP)) as usize;
+    let first_y = -(rows as f32 - 1.) * (BRICK_HEIGHT + BRICK_GAP) / 2.;
+
+    for column in 0..BRICK_COLUMNS {
+        let x = window.width() / 2.
+            - BRICKS_FROM_EDGE_MARGIN
+            - column as f32 * (BRICK_WIDTH + BRICK_GAP);
+        for row in 0..rows {
+            let y = first_y + row as f32 * (BRICK_HEIGHT + BRICK_GAP);
+            spawn_brick(&mut commands, Vec3::new(x, y, 0.));
+        }
+    }
+}
+
+pub fn spawn_brick(commands: &mut Commands, translation: Vec3) {
+    commands.spawn((
+        MatchEntity,
+        Brick,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.8, 0.5, 0.4),
+                custom_size: Some(Vec2::new(BRICK_WIDTH, BRICK_HEIGHT)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        PlayerCollider {
+            width: BRICK_WIDTH,
+            height: BRICK_HEIGHT,
+        },
+    ));
+}
+
+fn brick_hit_system(
+    mut commands: Commands,
+    mut obstacle_events: EventReader<ObstacleHitEvent>,
+    mut app_state: ResMut<State<GameState>>,
+    mut score_query: Query<&mut Score>,
+    bricks: Query<(), With<Brick>>,
+) {
+    let mut destroyed = HashSet::new();
+    for hit in obstacle_events.iter() {
+        if bricks.get(hit.obstacle).is_ok() && destroyed.insert(hit.obstacle) {
+            commands.entity(hit.obstacle).despawn();
+            if let Ok(mut score) = score_query.get_single_mut() {
+                score.player1_score += 1;
+            }
+        }
+    }
+
+    if !destroyed.is_empty() && destroyed.len() == bricks.iter().count() {
+        // Wynik mógł w tej samej klatce osiągnąć limit
+        let _ = app_state.set(GameState::MatchOver);
+    }
+}
+
+impl Plugin for BreakoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_bricks))
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(brick_hit_system));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use bevy::math::Vec2;
+
+// Jednorodna siatka - każdy obiekt trafia do wszystkich komórek, które przykrywa jego AABB
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<T>>,
+}
+
+impl<T: Copy + Ord> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // Komórki nieużyte w poprzedniej klatce znikają, np. po piłkach lecących poza boisko;
+        // pozostałe zostają puste, żeby nie alokować ich co klatkę
+        self.cells.retain(|_, cell| !cell.is_empty());
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    fn cell_of(&self, point: Vec2) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, item: T, min: Vec2, max: Vec2) {
+        let (min_x, min_y) = self.cell_of(min);
+        let (max_x, max_y) = self.cell_of(max);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(item);
+            }
+        }
+    }
+
+    // Zastępuje zawartość out obiektami z przykrytych komórek, każdym raz
+    pub fn query(&self, min: Vec2, max: Vec2, out: &mut Vec<T>) {
+        out.clear();
+        let (min_x, min_y) = self.cell_of(min);
+        let (max_x, max_y) = self.cell_of(max);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(cell);
+                }
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_returns_each_item_once() {
+        let mut grid = SpatialGrid::new(10.);
+        grid.insert(1, Vec2::ZERO, Vec2::splat(25.));
+        grid.insert(2, Vec2::splat(12.), Vec2::splat(14.));
+        let mut out = vec![7];
+        grid.query(Vec2::ZERO, Vec2::splat(30.), &mut out);
+        assert_eq!(out, [1, 2]);
+    }
+
+    #[test]
+    fn clear_drops_unused_cells() {
+        let mut grid = SpatialGrid::new(10.);
+        for step in 0..100 {
+            grid.clear();
+            let pos = Vec2::splat(step as f32 * 50.);
+            grid.insert(1, pos, pos);
+        }
+        grid.clear();
+        assert!(grid.cells.len() <= 1);
+    }
+}
@@ -1,7 +1,14 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
-use crate::{BallSound, GameState, Velocity};
+use crate::{
+    broadphase::SpatialGrid, player::Player, time_control::simulation_run_criteria, BallSound,
+    GameState, MatchSetup, Velocity,
+};
+
+const GRID_CELL_SIZE: f32 = 64.;
+const EDGE_DEFLECTION: f32 = 0.5;
+const MAX_BOUNCE_Y: f32 = 0.8;
 
 #[derive(Component)]
 pub struct BallCollider {
@@ -16,35 +23,181 @@ pub struct PlayerCollider {
 
 pub struct CollisionPlugin;
 
+// Odbicie od paletki gracza - liczy się do wymiany, statystyk i zmęczenia AI
+pub struct BallHitEvent {
+    pub ball: Entity,
+    pub paddle: Entity,
+}
+
+// Trafienie w inną przeszkodę z PlayerCollider, np. cegłę albo cel treningowy
+pub struct ObstacleHitEvent {
+    pub ball: Entity,
+    pub obstacle: Entity,
+}
+
 #[derive(SystemLabel)]
 pub struct CollisionPhase;
 
+#[derive(SystemLabel)]
+pub struct BroadphasePhase;
+
+#[derive(Resource)]
+pub struct CollisionGrid {
+    paddles: SpatialGrid<Entity>,
+    balls: SpatialGrid<Entity>,
+}
+
+impl Default for CollisionGrid {
+    fn default() -> Self {
+        Self {
+            paddles: SpatialGrid::new(GRID_CELL_SIZE),
+            balls: SpatialGrid::new(GRID_CELL_SIZE),
+        }
+    }
+}
+
+fn ball_bounds(b_tr: &Transform, b_col: &BallCollider) -> (Vec3, Vec3) {
+    (
+        b_tr.transform_point(Vec3::new(-b_col.radius, -b_col.radius, 0.0)),
+        b_tr.transform_point(Vec3::new(b_col.radius, b_col.radius, 0.0)),
+    )
+}
+
+fn player_bounds(p_tr: &Transform, p_col: &PlayerCollider) -> (Vec3, Vec3) {
+    (
+        p_tr.transform_point(Vec3::new(-p_col.width / 2., -p_col.height / 2., 0.0)),
+        p_tr.transform_point(Vec3::new(p_col.width / 2., p_col.height / 2., 0.0)),
+    )
+}
+
+fn rebuild_grid_system(
+    mut grid: ResMut<CollisionGrid>,
+    ball_query: Query<(Entity, &Transform, &BallCollider)>,
+    player_query: Query<(Entity, &Transform, &PlayerCollider)>,
+) {
+    grid.paddles.clear();
+    grid.balls.clear();
+
+    for (paddle, p_tr, p_col) in player_query.iter() {
+        let (min, max) = player_bounds(p_tr, p_col);
+        grid.paddles.insert(paddle, min.truncate(), max.truncate());
+    }
+    for (ball, b_tr, b_col) in ball_query.iter() {
+        let (min, max) = ball_bounds(b_tr, b_col);
+        grid.balls.insert(ball, min.truncate(), max.truncate());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn ball_player_collider_system(
-    mut ball_query: Query<(&Transform, &mut Velocity, &BallCollider)>,
-    player_query: Query<(&Transform, &PlayerCollider)>,
+    grid: Res<CollisionGrid>,
+    mut ball_query: Query<(Entity, &mut Transform, &mut Velocity, &BallCollider)>,
+    player_query: Query<(&Transform, &PlayerCollider, Option<&Player>), Without<BallCollider>>,
+    mut hit_events: EventWriter<BallHitEvent>,
+    mut obstacle_events: EventWriter<ObstacleHitEvent>,
+    mut candidates: Local<Vec<Entity>>,
     audio: Res<Audio>,
     ball_sound: Res<BallSound>,
 ) {
-    for (b_tr, mut b_vel, b_col) in ball_query.iter_mut() {
-        for (p_tr, p_col) in player_query.iter() {
-            let pl_bot_left =
-                p_tr.transform_point(Vec3::new(-p_col.width / 2., -p_col.height / 2., 0.0));
-            let pl_top_right =
-                p_tr.transform_point(Vec3::new(p_col.width / 2., p_col.height / 2., 0.0));
+    for (ball, mut b_tr, mut b_vel, b_col) in ball_query.iter_mut() {
+        let (b_bot_left, b_top_right) = ball_bounds(&b_tr, b_col);
+
+        grid.paddles.query(
+            b_bot_left.truncate(),
+            b_top_right.truncate(),
+            &mut candidates,
+        );
 
-            let b_bot_left = b_tr.transform_point(Vec3::new(-b_col.radius, -b_col.radius, 0.0));
-            let b_top_right = b_tr.transform_point(Vec3::new(b_col.radius, b_col.radius, 0.0));
+        for &paddle in candidates.iter() {
+            let Ok((p_tr, p_col, player)) = player_query.get(paddle) else {
+                continue;
+            };
+            let (pl_bot_left, pl_top_right) = player_bounds(p_tr, p_col);
+            let (b_bot_left, b_top_right) = ball_bounds(&b_tr, b_col);
 
             if !(pl_top_right.x < b_bot_left.x
                 || b_top_right.x < pl_bot_left.x
                 || pl_top_right.y < b_bot_left.y
                 || b_top_right.y < pl_bot_left.y)
             {
-                *b_vel = Velocity {
-                    direction: Vec2::new(b_vel.direction.x * -1., b_vel.direction.y),
-                    speed: b_vel.speed * 1.1
+                // Wypychamy piłkę na stronę paletki, po której jest jej środek,
+                // żeby nie utknęła w środku przy kolejnych klatkach
+                let away = if b_tr.translation.x < p_tr.translation.x {
+                    b_tr.translation.x = pl_bot_left.x - b_col.radius;
+                    -1.
+                } else {
+                    b_tr.translation.x = pl_top_right.x + b_col.radius;
+                    1.
                 };
-                audio.play(ball_sound.audio_handle.clone());
+
+                // Uderzenie bliżej krawędzi paletki mocniej podkręca piłkę w pionie;
+                // prędkość po odbiciu ustala krzywa z MatchRules w module piłki
+                if b_vel.direction.x * away < 0. {
+                    let offset = ((b_tr.translation.y - p_tr.translation.y) / (p_col.height / 2.))
+                        .clamp(-1., 1.);
+                    let y = (b_vel.direction.y + offset * EDGE_DEFLECTION)
+                        .clamp(-MAX_BOUNCE_Y, MAX_BOUNCE_Y);
+                    b_vel.direction = Vec2::new(away * (1. - y * y).sqrt(), y);
+                    if player.is_some() {
+                        hit_events.send(BallHitEvent { ball, paddle });
+                    } else {
+                        obstacle_events.send(ObstacleHitEvent {
+                            ball,
+                            obstacle: paddle,
+                        });
+                    }
+                    audio.play(ball_sound.audio_handle.clone());
+                }
+            }
+        }
+    }
+}
+
+fn ball_ball_collider_system(
+    grid: Res<CollisionGrid>,
+    mut ball_query: Query<(Entity, &Transform, &mut Velocity, &BallCollider)>,
+    mut candidates: Local<Vec<Entity>>,
+    mut bounces: Local<Vec<(Entity, Vec2)>>,
+) {
+    bounces.clear();
+    for (a, a_tr, a_vel, a_col) in ball_query.iter() {
+        let (a_bot_left, a_top_right) = ball_bounds(a_tr, a_col);
+
+        grid.balls.query(
+            a_bot_left.truncate(),
+            a_top_right.truncate(),
+            &mut candidates,
+        );
+
+        for &b in candidates.iter() {
+            // Każdą parę sprawdzamy tylko raz
+            if b.to_bits() <= a.to_bits() {
+                continue;
+            }
+            let Ok((_, b_tr, b_vel, b_col)) = ball_query.get(b) else {
+                continue;
+            };
+
+            let delta = (b_tr.translation - a_tr.translation).truncate();
+            let min_distance = a_col.radius + b_col.radius;
+            if delta == Vec2::ZERO || delta.length_squared() > min_distance * min_distance {
+                continue;
+            }
+
+            let normal = delta.normalize();
+            let relative = b_vel.direction * b_vel.speed - a_vel.direction * a_vel.speed;
+            if relative.dot(normal) < 0. {
+                bounces.push((a, normal));
+                bounces.push((b, -normal));
+            }
+        }
+    }
+
+    for &(ball, normal) in bounces.iter() {
+        if let Ok((_, _, mut vel, _)) = ball_query.get_mut(ball) {
+            let along_normal = vel.direction.dot(normal);
+            if along_normal > 0. {
+                vel.direction -= 2. * along_normal * normal;
             }
         }
     }
@@ -52,19 +205,28 @@ fn ball_player_collider_system(
 
 fn ball_wall_collider_system(
     windows: Res<Windows>,
-    mut query: Query<(&Transform, &mut Velocity, &BallCollider)>,
+    setup: Res<MatchSetup>,
+    mut query: Query<(&mut Transform, &mut Velocity, &BallCollider)>,
 ) {
     let window = windows.get_primary().unwrap();
+    let half_width = window.width() / 2.;
+    let half_height = window.height() / 2.;
 
-    for (b_tr, mut b_vel, b_col) in query.iter_mut() {
-        let b_bot_left = b_tr.transform_point(Vec3::new(-b_col.radius, -b_col.radius, 0.0));
-        let b_top_right = b_tr.transform_point(Vec3::new(b_col.radius, b_col.radius, 0.0));
+    for (mut b_tr, mut b_vel, b_col) in query.iter_mut() {
+        let (b_bot_left, b_top_right) = ball_bounds(&b_tr, b_col);
 
-        if b_top_right.y > window.height() / 2. || b_bot_left.y < -window.height() / 2. {
-            *b_vel = Velocity {
-                direction: Vec2::new(b_vel.direction.x, b_vel.direction.y * -1.),
-                ..*b_vel
-            }
+        // Piłka wraca na krawędź areny, inaczej przy dużej prędkości mogłaby z niej wylecieć
+        if b_top_right.y > half_height {
+            b_tr.translation.y = half_height - b_col.radius;
+            b_vel.direction.y = -b_vel.direction.y.abs();
+        } else if b_bot_left.y < -half_height {
+            b_tr.translation.y = -half_height + b_col.radius;
+            b_vel.direction.y = b_vel.direction.y.abs();
+        }
+
+        if setup.mode.right_wall_solid() && b_top_right.x > half_width {
+            b_tr.translation.x = half_width - b_col.radius;
+            b_vel.direction.x = -b_vel.direction.x.abs();
         }
     }
 }
@@ -77,12 +239,25 @@ fn setup_assets(mut commands: Commands, server: Res<AssetServer>) {
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_system(ball_wall_collider_system)
-                .with_system(ball_player_collider_system)
-                .label(CollisionPhase),
-        )
-        .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_assets));
+        app.add_event::<BallHitEvent>()
+            .add_event::<ObstacleHitEvent>()
+            .init_resource::<CollisionGrid>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
+                    .with_system(rebuild_grid_system)
+                    .label(BroadphasePhase)
+                    .after("pm")
+                    .before(CollisionPhase),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
+                    .with_system(ball_wall_collider_system)
+                    .with_system(ball_player_collider_system.after(ball_wall_collider_system))
+                    .with_system(ball_ball_collider_system.before(ball_player_collider_system))
+                    .label(CollisionPhase),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_assets));
     }
 }
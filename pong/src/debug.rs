@@ -0,0 +1,282 @@
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContext};
+
+use crate::{
+    ai::{AiProfiles, MAX_REACTION_DELAY},
+    ball::spawn_ball,
+    collisions::{BallCollider, PlayerCollider},
+    player::{AiDifficulty, AiTarget},
+    MatchRules, Velocity,
+};
+
+const FPS_COUNTER_KEY: KeyCode = KeyCode::F2;
+const VELOCITY_ARROW_SCALE: f32 = 0.2;
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+pub struct DebugPlugin;
+
+#[derive(Resource)]
+struct DebugOverlay {
+    enabled: bool,
+    show_colliders: bool,
+    show_velocity: bool,
+    show_ai_target: bool,
+    stress_balls: usize,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            show_colliders: true,
+            show_velocity: true,
+            show_ai_target: true,
+            stress_balls: 100,
+        }
+    }
+}
+
+// Licznik FPS działa niezależnie od nakładki, także poza meczem
+#[derive(Resource, Default)]
+struct FpsCounter {
+    enabled: bool,
+}
+
+fn toggle_overlay_system(
+    mut overlay: ResMut<DebugOverlay>,
+    mut fps_counter: ResMut<FpsCounter>,
+    input: Res<Input<KeyCode>>,
+) {
+    if input.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+    }
+    if input.just_pressed(FPS_COUNTER_KEY) {
+        fps_counter.enabled = !fps_counter.enabled;
+    }
+}
+
+fn draw_fps_counter(
+    mut egui_context: ResMut<EguiContext>,
+    fps_counter: Res<FpsCounter>,
+    diagnostics: Res<Diagnostics>,
+) {
+    if !fps_counter.enabled {
+        return;
+    }
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+
+    egui::Area::new("fps_counter")
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10., -10.))
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("FPS: {fps:.0}"));
+        });
+}
+
+fn draw_debug_shapes(
+    mut egui_context: ResMut<EguiContext>,
+    overlay: Res<DebugOverlay>,
+    windows: Res<Windows>,
+    ball_query: Query<(&Transform, &BallCollider, &Velocity)>,
+    paddle_query: Query<(&Transform, &PlayerCollider)>,
+    ai_query: Query<(&Transform, &AiTarget)>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let window = windows.get_primary().unwrap();
+    // Kamera 2D patrzy na środek okna, a oś y w egui rośnie w dół
+    let to_screen =
+        |pos: Vec2| egui::pos2(pos.x + window.width() / 2., window.height() / 2. - pos.y);
+
+    let painter = egui_context.ctx_mut().layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("debug_overlay"),
+    ));
+    let collider_stroke = egui::Stroke::new(1.0, egui::Color32::GREEN);
+    let velocity_stroke = egui::Stroke::new(1.0, egui::Color32::YELLOW);
+    let target_stroke = egui::Stroke::new(1.0, egui::Color32::RED);
+
+    if overlay.show_colliders {
+        for (transform, collider) in paddle_query.iter() {
+            let center = transform.translation.truncate();
+            let half_size = Vec2::new(collider.width, collider.height) / 2.;
+            painter.rect_stroke(
+                egui::Rect::from_two_pos(
+                    to_screen(center - half_size),
+                    to_screen(center + half_size),
+                ),
+                0.0,
+                collider_stroke,
+            );
+        }
+    }
+
+    for (transform, collider, velocity) in ball_query.iter() {
+        let center = transform.translation.truncate();
+        if overlay.show_colliders {
+            painter.rect_stroke(
+                egui::Rect::from_center_size(
+                    to_screen(center),
+                    egui::Vec2::splat(collider.radius * 2.),
+                ),
+                0.0,
+                collider_stroke,
+            );
+        }
+        if overlay.show_velocity {
+            let tip = center + velocity.direction * velocity.speed * VELOCITY_ARROW_SCALE;
+            painter.line_segment([to_screen(center), to_screen(tip)], velocity_stroke);
+        }
+    }
+
+    if overlay.show_ai_target {
+        for (transform, target) in ai_query.iter() {
+            if let Some(target_y) = target.0 {
+                let paddle = transform.translation.truncate();
+                let target = Vec2::new(paddle.x, target_y);
+                painter.circle_stroke(to_screen(target), 6.0, target_stroke);
+                painter.line_segment([to_screen(paddle), to_screen(target)], target_stroke);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_debug_window(
+    mut commands: Commands,
+    mut egui_context: ResMut<EguiContext>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut ai_difficulty: ResMut<AiDifficulty>,
+    mut ai_profiles: ResMut<AiProfiles>,
+    mut profile_status: Local<Option<String>>,
+    rules: Res<MatchRules>,
+    mut ball_query: Query<&mut Velocity, With<BallCollider>>,
+    mut paddle_query: Query<(&mut PlayerCollider, &mut Sprite)>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    egui::Window::new("Debug")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(10., 10.))
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.checkbox(&mut overlay.show_colliders, "Colliders");
+            ui.checkbox(&mut overlay.show_velocity, "Velocity");
+            ui.checkbox(&mut overlay.show_ai_target, "AI target");
+            ui.separator();
+
+            if let Some(mut speed) = ball_query.iter().next().map(|velocity| velocity.speed) {
+                if ui
+                    .add(
+                        egui::Slider::new(&mut speed, 0.0..=rules.speed.max_speed)
+                            .text("Ball speed"),
+                    )
+                    .changed()
+                {
+                    for mut velocity in ball_query.iter_mut() {
+                        velocity.speed = speed;
+                    }
+                }
+            }
+
+            if let Some(mut height) = paddle_query
+                .iter()
+                .next()
+                .map(|(collider, _)| collider.height)
+            {
+                if ui
+                    .add(egui::Slider::new(&mut height, 10.0..=400.0).text("Paddle size"))
+                    .changed()
+                {
+                    for (mut collider, mut sprite) in paddle_query.iter_mut() {
+                        collider.height = height;
+                        sprite.custom_size = Some(Vec2::new(collider.width, height));
+                    }
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::Slider::new(&mut overlay.stress_balls, 1..=5000).text("Balls"));
+                if ui.button("Spawn").clicked() {
+                    // Kierunki rozkładamy złotym kątem, żeby piłki nie leciały w jednej linii
+                    for i in 0..overlay.stress_balls {
+                        let angle = i as f32 * GOLDEN_ANGLE;
+                        spawn_ball(
+                            &mut commands,
+                            Vec3::ZERO,
+                            Vec2::new(angle.cos(), angle.sin()),
+                            rules.speed.base_speed,
+                        );
+                    }
+                }
+            });
+            ui.label(format!("Ball count: {}", ball_query.iter().len()));
+            ui.separator();
+
+            egui::ComboBox::from_label("AI difficulty")
+                .selected_text(format!("{:?}", *ai_difficulty))
+                .show_ui(ui, |ui| {
+                    for difficulty in AiDifficulty::ALL {
+                        ui.selectable_value(
+                            &mut *ai_difficulty,
+                            difficulty,
+                            format!("{difficulty:?}"),
+                        );
+                    }
+                });
+
+            ui.collapsing("AI profile", |ui| {
+                let profile = ai_profiles.get_mut(*ai_difficulty);
+                ui.add(
+                    egui::Slider::new(&mut profile.reaction_delay, 0.0..=MAX_REACTION_DELAY)
+                        .text("Reaction delay"),
+                );
+                ui.add(egui::Slider::new(&mut profile.deadzone, 0.0..=60.0).text("Deadzone"));
+                ui.add(egui::Slider::new(&mut profile.noise_base, 0.0..=100.0).text("Noise"));
+                ui.add(
+                    egui::Slider::new(&mut profile.noise_per_speed, 0.0..=0.2)
+                        .text("Noise per speed"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut profile.edge_hit_chance, 0.0..=1.0)
+                        .text("Edge hit chance"),
+                );
+                ui.add(egui::Slider::new(&mut profile.edge_offset, 0.0..=1.0).text("Edge offset"));
+                ui.add(
+                    egui::Slider::new(&mut profile.fatigue_per_hit, 0.0..=0.2)
+                        .text("Fatigue per hit"),
+                );
+                ui.add(egui::Slider::new(&mut profile.max_fatigue, 0.0..=2.0).text("Max fatigue"));
+
+                if ui.button("Save profiles").clicked() {
+                    let path = AiProfiles::path();
+                    *profile_status = Some(match ai_profiles.save(&path) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(err) => format!("Failed to save profiles: {err}"),
+                    });
+                }
+                if let Some(status) = profile_status.as_ref() {
+                    ui.label(status);
+                }
+            });
+        });
+}
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<FpsCounter>()
+            .add_system(toggle_overlay_system)
+            .add_system(draw_fps_counter)
+            .add_system(draw_debug_shapes)
+            .add_system(draw_debug_window);
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::{
+    prelude::*,
+    window::{WindowMode, WindowResized},
+};
+
+use crate::{
+    ball::Ball,
+    breakout::Brick,
+    player::{Player, Side},
+    practice::Target,
+};
+
+const PLAYER_FROM_EDGE_MARGIN: f32 = 40.;
+const FULLSCREEN_KEY: KeyCode = KeyCode::F11;
+
+pub struct LayoutPlugin;
+
+// Sloty 0 i 2 stoją przy krawędziach, 1 i 3 w połowie drogi do środka
+pub fn paddle_slot(slot: usize, arena_width: f32) -> (Side, f32) {
+    let edge_x = arena_width / 2. - PLAYER_FROM_EDGE_MARGIN;
+    match slot {
+        0 => (Side::Left, -edge_x),
+        1 => (Side::Left, -edge_x / 2.),
+        2 => (Side::Right, edge_x),
+        _ => (Side::Right, edge_x / 2.),
+    }
+}
+
+fn arena_size(window: &Window) -> Vec2 {
+    Vec2::new(window.width(), window.height())
+}
+
+// Arena to całe okno, więc po zmianie rozmiaru przesuwamy wszystko do nowych krawędzi;
+// prędkości piłek zostają bez zmian
+fn relayout_system(
+    mut resized_events: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut arena: Local<Option<Vec2>>,
+    mut paddle_query: Query<(&Player, &mut Transform)>,
+    mut ball_query: Query<&mut Transform, (With<Ball>, Without<Player>)>,
+    mut brick_query: Query<&mut Transform, (With<Brick>, Without<Player>, Without<Ball>)>,
+    mut target_query: Query<
+        &mut Transform,
+        (With<Target>, Without<Player>, Without<Ball>, Without<Brick>),
+    >,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let resized = resized_events.iter().any(|event| event.id == window.id());
+    let size = arena_size(window);
+    let Some(old_size) = arena.replace(size) else {
+        return;
+    };
+    if !resized || old_size == size || old_size.min_element() <= 0. || size.min_element() <= 0. {
+        return;
+    }
+    let scale = size / old_size;
+
+    for (player, mut transform) in paddle_query.iter_mut() {
+        transform.translation.x = paddle_slot(player.id, size.x).1;
+        transform.translation.y *= scale.y;
+    }
+    for mut transform in ball_query.iter_mut() {
+        transform.translation.x *= scale.x;
+        transform.translation.y *= scale.y;
+    }
+    // Cegły trzymają stały odstęp od prawej ściany
+    for mut transform in brick_query.iter_mut() {
+        transform.translation.x += (size.x - old_size.x) / 2.;
+        transform.translation.y *= scale.y;
+    }
+    for mut transform in target_query.iter_mut() {
+        transform.translation.x *= scale.x;
+        transform.translation.y *= scale.y;
+    }
+}
+
+fn fullscreen_system(input: Res<Input<KeyCode>>, mut windows: ResMut<Windows>) {
+    if !input.just_pressed(FULLSCREEN_KEY) {
+        return;
+    }
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    let mode = match window.mode() {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+    window.set_mode(mode);
+}
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(relayout_system.before("pm"))
+            .add_system(fullscreen_system);
+    }
+}
@@ -1,21 +1,51 @@
-use ball::BallPlugin;
+use ball::{BallPlugin, SpeedCurve, SpeedProfile, BALL_SPEED};
+use breakout::BreakoutPlugin;
 //Dla wersji FixedTimestep
 //use bevy::{ecs::schedule::ShouldRun, time::FixedTimestep};
-use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::prelude::*;
 use bevy_egui::egui::{Frame, Pos2};
 use bevy_egui::{egui, EguiContext, EguiPlugin};
 use bevy_kira_audio::prelude::*;
-use collisions::{CollisionPhase, CollisionPlugin};
-use player::{AiInputComp, PlayerBundle, PlayerInputComp, PlayerPlugin};
+use collisions::{BroadphasePhase, CollisionPhase, CollisionPlugin};
+use debug::DebugPlugin;
+use layout::{paddle_slot, LayoutPlugin};
+use menu::MenuPlugin;
+use player::{Controller, KeyBindings, PlayerBundle, PlayerPlugin};
+use practice::PracticePlugin;
+use profile::PongProfilePlugin;
+use profiles::ProfilesPlugin;
+use save::{SaveMatchEvent, SavePlugin, SaveStatus};
+use scoreboard::ScoreboardPlugin;
+use serde::{Deserialize, Serialize};
+use stats::StatsPlugin;
+use theme::ThemePlugin;
+use time_control::{simulation_run_criteria, TimeControl, TimeControlPlugin};
+use tournament::TournamentPlugin;
 
+mod ai;
 mod ball;
+mod breakout;
+mod broadphase;
 mod collisions;
+mod debug;
+mod layout;
+mod menu;
 mod player;
+mod practice;
+mod profile;
+mod save;
+mod scoreboard;
+#[cfg(test)]
+mod sim_tests;
+mod stats;
+mod theme;
+mod time_control;
+mod tournament;
 
-const PLAYER_FROM_EDGE_MARGIN: f32 = 40.;
+const SCORE_LIMIT: i32 = 10;
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 struct Velocity {
     direction: Vec2,
     speed: f32,
@@ -28,67 +58,188 @@ struct Score {
 }
 
 #[derive(Component)]
-struct ScoreText;
+struct MatchEntity;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum GameMode {
+    OneVsOne,
+    TwoVsTwo,
+    VsAi,
+    Breakout,
+    WallPractice,
+    TargetDrill,
+}
+
+impl GameMode {
+    const ALL: [GameMode; 6] = [
+        GameMode::OneVsOne,
+        GameMode::TwoVsTwo,
+        GameMode::VsAi,
+        GameMode::Breakout,
+        GameMode::WallPractice,
+        GameMode::TargetDrill,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            GameMode::OneVsOne => "1v1",
+            GameMode::TwoVsTwo => "2v2",
+            GameMode::VsAi => "vs AI",
+            GameMode::Breakout => "Breakout",
+            GameMode::WallPractice => "Wall practice",
+            GameMode::TargetDrill => "Target drill",
+        }
+    }
+
+    // Sloty 0 i 1 są po lewej, 2 i 3 po prawej; 0 i 2 przy krawędziach
+    fn slots(self) -> &'static [usize] {
+        match self {
+            GameMode::OneVsOne | GameMode::VsAi => &[0, 2],
+            GameMode::TwoVsTwo => &[0, 1, 2, 3],
+            GameMode::Breakout | GameMode::WallPractice | GameMode::TargetDrill => &[0],
+        }
+    }
+
+    fn default_controllers(self, bindings: &KeyBindings) -> Vec<Controller> {
+        match self {
+            GameMode::VsAi => vec![bindings.controller(0), Controller::Ai],
+            GameMode::TwoVsTwo => vec![
+                Controller::Ai,
+                bindings.controller(1),
+                bindings.controller(2),
+                bindings.controller(3),
+            ],
+            _ => self
+                .slots()
+                .iter()
+                .map(|&slot| bindings.controller(slot))
+                .collect(),
+        }
+    }
+
+    fn right_wall_solid(self) -> bool {
+        self == GameMode::Breakout || self.is_practice()
+    }
+
+    // Tryby treningowe kończy czas, a nie limit punktów
+    fn is_practice(self) -> bool {
+        matches!(self, GameMode::WallPractice | GameMode::TargetDrill)
+    }
+
+    // 2v2 ma więcej odbić w wymianie, a w Breakout piłka przyspiesza też na cegłach
+    fn speed_curve(self) -> SpeedCurve {
+        let (max_speed, profile) = match self {
+            GameMode::OneVsOne => (1600., SpeedProfile::Linear { per_hit: 40. }),
+            GameMode::TwoVsTwo => (
+                1600.,
+                SpeedProfile::Stepped {
+                    per_step: 150.,
+                    hits_per_step: 4,
+                },
+            ),
+            GameMode::VsAi => (1400., SpeedProfile::Linear { per_hit: 30. }),
+            GameMode::Breakout => (1000., SpeedProfile::Exponential { factor: 1.03 }),
+            GameMode::WallPractice | GameMode::TargetDrill => {
+                (1200., SpeedProfile::Linear { per_hit: 20. })
+            }
+        };
+        SpeedCurve {
+            base_speed: BALL_SPEED,
+            max_speed,
+            profile,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+struct MatchSetup {
+    mode: GameMode,
+    controllers: Vec<Controller>,
+}
+
+impl MatchSetup {
+    fn for_mode(mode: GameMode, bindings: &KeyBindings) -> Self {
+        Self {
+            mode,
+            controllers: mode.default_controllers(bindings),
+        }
+    }
+}
+
+impl Default for MatchSetup {
+    fn default() -> Self {
+        Self::for_mode(GameMode::TwoVsTwo, &KeyBindings::default())
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+struct MatchRules {
+    score_limit: i32,
+    #[serde(default)]
+    speed: SpeedCurve,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            score_limit: SCORE_LIMIT,
+            speed: MatchSetup::default().mode.speed_curve(),
+        }
+    }
+}
 
 #[derive(Resource)]
 struct BallSound {
     audio_handle: Handle<AudioSource>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum GameState {
+    MainMenu,
     InGame,
     Paused,
+    MatchOver,
+    Tournament,
 }
 
 fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
-fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
-                justify_content: JustifyContent::Center,
-                ..default()
-            },
-            ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn((
-                ScoreText,
-                TextBundle::from_section(
-                    "0 - 0",
-                    TextStyle {
-                        font: asset_server.load("fonts/FiraCode-Regular.ttf"),
-                        font_size: 60.0,
-                        color: Color::WHITE,
-                    },
-                )
-                .with_text_alignment(TextAlignment::TOP_CENTER)
-                .with_style(Style {
-                    size: Size::new(Val::Undefined, Val::Px(25.)),
-                    margin: UiRect {
-                        left: Val::Auto,
-                        right: Val::Auto,
-                        ..default()
-                    },
-                    ..default()
-                }),
-            ));
-        });
+fn setup_score(mut commands: Commands) {
+    commands.spawn((
+        MatchEntity,
+        Score {
+            player1_score: 0,
+            player2_score: 0,
+        },
+    ));
+}
 
-    commands.spawn(Score {
-        player1_score: 0,
-        player2_score: 0,
-    });
+fn despawn_match_entities(mut commands: Commands, query: Query<Entity, With<MatchEntity>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
-fn update_score_ui(mut text_query: Query<&mut Text, With<ScoreText>>, score_query: Query<&Score>) {
-    let score = score_query.get_single().unwrap();
-    for mut text in text_query.iter_mut() {
-        text.sections[0].value = format!("{} - {}", score.player1_score, score.player2_score);
+fn match_over_system(
+    mut app_state: ResMut<State<GameState>>,
+    score_query: Query<&Score>,
+    rules: Res<MatchRules>,
+    setup: Res<MatchSetup>,
+) {
+    let Ok(score) = score_query.get_single() else {
+        return;
+    };
+    // W Breakout punkty lewej strony to zbite cegły, a mecz kończą stracone piłki
+    let limit_reached = match setup.mode {
+        GameMode::Breakout => score.player2_score >= rules.score_limit,
+        GameMode::WallPractice | GameMode::TargetDrill => false,
+        _ => score.player1_score >= rules.score_limit || score.player2_score >= rules.score_limit,
+    };
+    if limit_reached {
+        // Tryb gry mógł już zakończyć mecz w tej klatce
+        let _ = app_state.set(GameState::MatchOver);
     }
 }
 
@@ -100,46 +251,27 @@ fn update_score_ui(mut text_query: Query<&mut Text, With<ScoreText>>, score_quer
 //     }
 // }
 
-fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
+fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>, time_control: Res<TimeControl>) {
     for (mut transform, velocity) in query.iter_mut() {
         transform.translation +=
-            velocity.direction.extend(0.0) * velocity.speed * time.delta_seconds();
+            velocity.direction.extend(0.0) * velocity.speed * time_control.delta_seconds();
     }
 }
 
-fn setup_players(mut commands: Commands, windows: Res<Windows>) {
+fn setup_players(mut commands: Commands, windows: Res<Windows>, setup: Res<MatchSetup>) {
     let window = windows.get_primary().unwrap();
-
-    let first_player_x = -window.width() / 2. + PLAYER_FROM_EDGE_MARGIN;
-    let second_player_x = window.width() / 2. - PLAYER_FROM_EDGE_MARGIN;
-
     let starting_y = 0.;
 
-    commands.spawn((
-        AiInputComp,
-        PlayerBundle::default().with_start_pos(Vec2::new(first_player_x, starting_y)),
-    ));
-
-    commands.spawn((
-        PlayerInputComp,
-        PlayerBundle::default()
-            .with_start_pos(Vec2::new(first_player_x / 2., starting_y))
-            .with_keys(KeyCode::E, KeyCode::D),
-    ));
-
-    commands.spawn((
-        PlayerInputComp,
-        PlayerBundle::default()
-            .with_start_pos(Vec2::new(second_player_x, starting_y))
-            .with_keys(KeyCode::I, KeyCode::K),
-    ));
-
-    commands.spawn((
-        PlayerInputComp,
-        PlayerBundle::default()
-            .with_start_pos(Vec2::new(second_player_x / 2., starting_y))
-            .with_keys(KeyCode::U, KeyCode::J),
-    ));
+    for (&slot, controller) in setup.mode.slots().iter().zip(setup.controllers.iter()) {
+        let (side, x) = paddle_slot(slot, window.width());
+        let mut paddle = commands.spawn((
+            MatchEntity,
+            PlayerBundle::default()
+                .with_slot(slot, side)
+                .with_start_pos(Vec2::new(x, starting_y)),
+        ));
+        controller.apply(&mut paddle);
+    }
 }
 
 fn pause_system(mut app_state: ResMut<State<GameState>>, input: Res<Input<KeyCode>>) {
@@ -151,14 +283,17 @@ fn pause_system(mut app_state: ResMut<State<GameState>>, input: Res<Input<KeyCod
             GameState::InGame => {
                 app_state.push(GameState::Paused).unwrap();
             }
+            GameState::MainMenu | GameState::MatchOver | GameState::Tournament => {}
         };
     }
 }
 
 fn draw_pause_menu(
     mut egui_context: ResMut<EguiContext>,
-    app_state: Res<State<GameState>>,
+    mut app_state: ResMut<State<GameState>>,
     windows: Res<Windows>,
+    mut save_events: EventWriter<SaveMatchEvent>,
+    save_status: Res<SaveStatus>,
 ) {
     if app_state.current() != &GameState::Paused {
         return;
@@ -218,6 +353,22 @@ fn draw_pause_menu(
             if title_bar_response.is_pointer_button_down_on() {}
 
             // Add the contents:
+            let content_rect = {
+                let mut rect = rect;
+                rect.min.y = title_bar_rect.max.y;
+                rect
+            }
+            .shrink(4.0);
+            let mut content_ui = ui.child_ui(content_rect, *ui.layout());
+            if content_ui.button("Save match").clicked() {
+                save_events.send(SaveMatchEvent);
+            }
+            if let Some(message) = save_status.message.as_ref() {
+                content_ui.label(message);
+            }
+            if content_ui.button("Main menu").clicked() {
+                app_state.replace(GameState::MainMenu).unwrap();
+            }
         });
 }
 
@@ -232,16 +383,38 @@ fn main() {
             ..default()
         }))
         .add_startup_system(setup_camera)
-        .add_startup_system(setup_players)
-        .add_state(GameState::InGame)
+        .add_state(GameState::MainMenu)
+        .init_resource::<MatchRules>()
+        .init_resource::<MatchSetup>()
+        .add_system_set(
+            SystemSet::on_enter(GameState::InGame)
+                .with_system(despawn_match_entities)
+                .with_system(setup_score)
+                .with_system(setup_players),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::MainMenu).with_system(despawn_match_entities),
+        )
         .add_plugin(EguiPlugin)
         .add_plugin(BallPlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(AudioPlugin)
         .add_plugin(CollisionPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(TournamentPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(TimeControlPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(BreakoutPlugin)
+        .add_plugin(PracticePlugin)
+        .add_system_set(
+            SystemSet::new()
+                .with_run_criteria(simulation_run_criteria)
+                .with_system(apply_velocity.before(BroadphasePhase)),
+        )
         .add_system_set(
             SystemSet::on_update(GameState::InGame)
-                .with_system(apply_velocity.before(CollisionPhase)),
+                .with_system(match_over_system.after(CollisionPhase)),
         )
         // Dla wykonywania systemów stałą ilość razy w ciągu sekundy
         // .add_system_set(
@@ -257,11 +430,14 @@ fn main() {
         //         ))
         //         .with_system(apply_velocity.before(CollisionPhase)),
         // )
-        .add_startup_system(setup_ui)
         .add_system(pause_system)
-        .add_system(update_score_ui)
         .add_system(draw_pause_menu)
-        .add_plugin(LogDiagnosticsPlugin::default())
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(DebugPlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(ScoreboardPlugin)
+        .add_plugin(LayoutPlugin)
+        .add_plugin(ProfilesPlugin { game: "pong" })
+        .add_plugin(PongProfilePlugin)
         .run();
 }
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::{
+    ball::{SpeedProfile, MAX_BALL_SPEED},
+    player::{AiDifficulty, Controller, KeyBindings},
+    theme::{PaletteId, VisualSettings, MAX_FONT_SCALE, MIN_FONT_SCALE},
+    tournament::Tournament,
+    GameMode, GameState, MatchRules, MatchSetup,
+};
+
+const MAX_SCORE_LIMIT: i32 = 21;
+
+pub struct MenuPlugin;
+
+fn controller_name(controller: Controller) -> String {
+    match controller {
+        Controller::Ai => "AI".to_string(),
+        Controller::Keys { up, down } => format!("{up:?}/{down:?}"),
+    }
+}
+
+fn draw_main_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut app_state: ResMut<State<GameState>>,
+    mut setup: ResMut<MatchSetup>,
+    mut rules: ResMut<MatchRules>,
+    mut ai_difficulty: ResMut<AiDifficulty>,
+    mut visuals: ResMut<VisualSettings>,
+    bindings: Res<KeyBindings>,
+) {
+    if app_state.current() != &GameState::MainMenu {
+        return;
+    }
+
+    let controller_options: Vec<Controller> = std::iter::once(Controller::Ai)
+        .chain(
+            bindings
+                .paddles
+                .iter()
+                .map(|&(up, down)| Controller::Keys { up, down }),
+        )
+        .collect();
+
+    egui::Window::new("Pong")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading("Mode");
+            ui.horizontal(|ui| {
+                for mode in GameMode::ALL {
+                    if ui
+                        .selectable_label(setup.mode == mode, mode.name())
+                        .clicked()
+                        && setup.mode != mode
+                    {
+                        *setup = MatchSetup::for_mode(mode, &bindings);
+                        rules.speed = mode.speed_curve();
+                    }
+                }
+            });
+            ui.separator();
+
+            ui.heading("Paddles");
+            let mode = setup.mode;
+            for (&slot, controller) in mode.slots().iter().zip(setup.controllers.iter_mut()) {
+                egui::ComboBox::from_label(format!("Paddle {}", slot + 1))
+                    .selected_text(controller_name(*controller))
+                    .show_ui(ui, |ui| {
+                        for &option in controller_options.iter() {
+                            ui.selectable_value(controller, option, controller_name(option));
+                        }
+                    });
+            }
+            ui.separator();
+
+            ui.heading("Rules");
+            ui.add(
+                egui::Slider::new(&mut rules.score_limit, 1..=MAX_SCORE_LIMIT).text("Score limit"),
+            );
+            ui.separator();
+
+            ui.heading("Ball speed");
+            let speed = &mut rules.speed;
+            ui.add(egui::Slider::new(&mut speed.base_speed, 100.0..=1000.0).text("Serve speed"));
+            ui.add(
+                egui::Slider::new(&mut speed.max_speed, speed.base_speed..=MAX_BALL_SPEED)
+                    .text("Max speed"),
+            );
+            // Suwak prędkości serwu może przeskoczyć zapamiętane maksimum
+            speed.max_speed = speed.max_speed.max(speed.base_speed);
+            egui::ComboBox::from_label("Rally profile")
+                .selected_text(speed.profile.name())
+                .show_ui(ui, |ui| {
+                    for profile in SpeedProfile::ALL {
+                        if ui
+                            .selectable_label(
+                                speed.profile.name() == profile.name(),
+                                profile.name(),
+                            )
+                            .clicked()
+                            && speed.profile.name() != profile.name()
+                        {
+                            speed.profile = profile;
+                        }
+                    }
+                });
+            match &mut speed.profile {
+                SpeedProfile::Linear { per_hit } => {
+                    ui.add(egui::Slider::new(per_hit, 0.0..=200.0).text("Per hit"));
+                }
+                SpeedProfile::Stepped {
+                    per_step,
+                    hits_per_step,
+                } => {
+                    ui.add(egui::Slider::new(per_step, 0.0..=500.0).text("Per step"));
+                    ui.add(egui::Slider::new(hits_per_step, 1..=10).text("Hits per step"));
+                }
+                SpeedProfile::Exponential { factor } => {
+                    ui.add(egui::Slider::new(factor, 1.0..=1.5).text("Factor"));
+                }
+            }
+            ui.separator();
+
+            egui::ComboBox::from_label("AI difficulty")
+                .selected_text(format!("{:?}", *ai_difficulty))
+                .show_ui(ui, |ui| {
+                    for difficulty in AiDifficulty::ALL {
+                        ui.selectable_value(
+                            &mut *ai_difficulty,
+                            difficulty,
+                            format!("{difficulty:?}"),
+                        );
+                    }
+                });
+            ui.separator();
+
+            ui.heading("Visuals");
+            let mut palette = visuals.palette;
+            egui::ComboBox::from_label("Palette")
+                .selected_text(palette.name())
+                .show_ui(ui, |ui| {
+                    for option in PaletteId::ALL {
+                        ui.selectable_value(&mut palette, option, option.name());
+                    }
+                });
+            if palette != visuals.palette {
+                visuals.palette = palette;
+            }
+            // Porównujemy wartość po suwaku, więc zmiana z klawiatury też się liczy
+            let mut font_scale = visuals.font_scale;
+            ui.add(
+                egui::Slider::new(&mut font_scale, MIN_FONT_SCALE..=MAX_FONT_SCALE)
+                    .text("Font scale"),
+            );
+            if font_scale != visuals.font_scale {
+                visuals.font_scale = font_scale;
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Start").clicked() {
+                    app_state.replace(GameState::InGame).unwrap();
+                }
+                if ui.button("Tournament").clicked() {
+                    app_state.replace(GameState::Tournament).unwrap();
+                }
+            });
+        });
+}
+
+fn draw_match_over_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut app_state: ResMut<State<GameState>>,
+    tournament: Option<Res<Tournament>>,
+) {
+    if app_state.current() != &GameState::MatchOver || tournament.is_some() {
+        return;
+    }
+
+    egui::Window::new("Next")
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0., -20.))
+        .title_bar(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Rematch").clicked() {
+                    app_state.replace(GameState::InGame).unwrap();
+                }
+                if ui.button("Main menu").clicked() {
+                    app_state.replace(GameState::MainMenu).unwrap();
+                }
+            });
+        });
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_main_menu)
+            .add_system(draw_match_over_menu);
+    }
+}
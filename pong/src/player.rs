@@ -1,19 +1,37 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    ball::{Ball},
-    collisions::PlayerCollider,
-    GameState,
+    ai::{load_ai_profiles_system, AiMind, AiProfiles, PerceivedBall, Perception},
+    ball::{Ball, GoalEvent, ServeEvent},
+    collisions::{BallHitEvent, PlayerCollider},
+    time_control::{simulation_run_criteria, TimeControl},
+    Velocity,
 };
 
 const PLAYERS_SPEED: f32 = 500.0;
 const PLAYER_WIDTH: f32 = 10.0;
 const PLAYER_HEIGHT: f32 = 120.0;
+pub const PADDLE_KEYS: [(KeyCode, KeyCode); 4] = [
+    (KeyCode::W, KeyCode::S),
+    (KeyCode::E, KeyCode::D),
+    (KeyCode::I, KeyCode::K),
+    (KeyCode::U, KeyCode::J),
+];
 pub struct PlayerPlugin;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
 #[derive(Component)]
-struct Player;
+pub struct Player {
+    pub id: usize,
+    pub side: Side,
+}
 
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
 pub enum PlayerInput {
@@ -27,6 +45,111 @@ pub struct PlayerInputComp;
 #[derive(Component)]
 pub struct AiInputComp;
 
+#[derive(Component, Default)]
+pub struct AiTarget(pub Option<f32>);
+
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl AiDifficulty {
+    pub const ALL: [AiDifficulty; 3] =
+        [AiDifficulty::Easy, AiDifficulty::Normal, AiDifficulty::Hard];
+
+    // Easy reaguje tylko na nadlatujące piłki, Normal śledzi średnią, Hard przewiduje odbicia
+    fn target_y(self, paddle: Vec3, balls: &[(Vec3, Vec2)], half_height: f32) -> Option<f32> {
+        let incoming = balls
+            .iter()
+            .filter(|(pos, dir)| (paddle.x - pos.x) * dir.x > 0.);
+
+        match self {
+            AiDifficulty::Easy => average_y(incoming.map(|(pos, _)| pos.y)),
+            AiDifficulty::Normal => average_y(balls.iter().map(|(pos, _)| pos.y)),
+            AiDifficulty::Hard => incoming
+                .map(|(pos, dir)| {
+                    (
+                        (paddle.x - pos.x) / dir.x,
+                        predict_intercept(pos.truncate(), *dir, paddle.x, half_height),
+                    )
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, y)| y)
+                .or(Some(0.)),
+        }
+    }
+}
+
+fn average_y(ys: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = ys.fold((0., 0), |(sum, count), y| (sum + y, count + 1));
+    (count != 0).then(|| sum / count as f32)
+}
+
+pub fn predict_intercept(pos: Vec2, dir: Vec2, target_x: f32, half_height: f32) -> f32 {
+    let y = pos.y + dir.y * (target_x - pos.x) / dir.x + half_height;
+    let period = 4. * half_height;
+    let y = y.rem_euclid(period);
+    if y > 2. * half_height {
+        period - y - half_height
+    } else {
+        y - half_height
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Controller {
+    Ai,
+    Keys { up: KeyCode, down: KeyCode },
+}
+
+impl Controller {
+    pub fn apply(self, paddle: &mut EntityCommands) {
+        match self {
+            Controller::Ai => {
+                let seed = paddle.id().to_bits();
+                paddle.remove::<PlayerInputComp>().insert((
+                    self,
+                    AiInputComp,
+                    AiTarget::default(),
+                    AiMind::new(seed),
+                    InputMap::<PlayerInput>::default(),
+                ));
+            }
+            Controller::Keys { up, down } => {
+                paddle.remove::<(AiInputComp, AiTarget, AiMind)>().insert((
+                    self,
+                    PlayerInputComp,
+                    InputMap::new([(up, PlayerInput::Up), (down, PlayerInput::Down)]),
+                ));
+            }
+        }
+    }
+}
+
+// Klawisze paletek z aktywnego profilu; domyślnie PADDLE_KEYS
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub paddles: [(KeyCode, KeyCode); 4],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            paddles: PADDLE_KEYS,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn controller(&self, slot: usize) -> Controller {
+        let (up, down) = self.paddles[slot % self.paddles.len()];
+        Controller::Keys { up, down }
+    }
+}
+
 #[derive(Bundle)]
 pub struct PlayerBundle {
     player: Player,
@@ -36,15 +159,12 @@ pub struct PlayerBundle {
 }
 
 impl PlayerBundle {
-    pub fn with_start_pos(mut self, pos: Vec2) -> Self {
-        self.sprite_bundle.transform.translation = pos.extend(0.);
+    pub fn with_slot(mut self, id: usize, side: Side) -> Self {
+        self.player = Player { id, side };
         self
     }
-    pub fn with_keys(mut self, up_key: KeyCode, down_key: KeyCode) -> Self {
-        self.input_manager_bundle
-            .input_map
-            .insert(up_key, PlayerInput::Up)
-            .insert(down_key, PlayerInput::Down);
+    pub fn with_start_pos(mut self, pos: Vec2) -> Self {
+        self.sprite_bundle.transform.translation = pos.extend(0.);
         self
     }
 }
@@ -52,7 +172,10 @@ impl PlayerBundle {
 impl Default for PlayerBundle {
     fn default() -> Self {
         Self {
-            player: Player,
+            player: Player {
+                id: 0,
+                side: Side::Left,
+            },
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
                     color: Color::rgb(0.8, 0.8, 1.0),
@@ -74,7 +197,7 @@ impl Default for PlayerBundle {
     }
 }
 
-fn player_movement_system(mut query: Query<(&mut Transform, &mut ActionState<PlayerInput>)>, time : Res<Time>) {
+fn player_movement_system(mut query: Query<(&mut Transform, &mut ActionState<PlayerInput>)>, time_control : Res<TimeControl>) {
     for (mut transform, action_state) in query.iter_mut() {
         let mut direction = Vec3::new(0.0, 0.0, 0.0);
 
@@ -84,24 +207,76 @@ fn player_movement_system(mut query: Query<(&mut Transform, &mut ActionState<Pla
             direction = Vec3::new(0.0, -1.0, 0.0);
         }
 
-        transform.translation += direction * PLAYERS_SPEED * time.delta_seconds();
+        transform.translation += direction * PLAYERS_SPEED * time_control.delta_seconds();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn player_ai_system(
-    mut ai_query: Query<(&Transform, &mut ActionState<PlayerInput>), With<AiInputComp>>,
-    ball_query: Query<&Transform, With<Ball>>,
+    mut ai_query: Query<
+        (
+            &Transform,
+            &PlayerCollider,
+            &mut ActionState<PlayerInput>,
+            &mut AiTarget,
+            &mut AiMind,
+        ),
+        With<AiInputComp>,
+    >,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    mut hit_events: EventReader<BallHitEvent>,
+    mut goal_events: EventReader<GoalEvent>,
+    mut serve_events: EventReader<ServeEvent>,
+    mut perception: Local<Perception>,
+    difficulty: Res<AiDifficulty>,
+    profiles: Res<AiProfiles>,
+    time_control: Res<TimeControl>,
+    windows: Res<Windows>,
 ) {
-    for (ai_transform, mut action_state) in ai_query.iter_mut() {
-        let balls = ball_query.iter();
-        let ball_num = balls.count();
-
-        let balls = ball_query.iter();
-        if ball_num != 0 {
-            let avg = balls.map(|transform| transform.translation.y).sum::<f32>() / ball_num as f32;
-            if ai_transform.translation.y > avg {
+    let window = windows.get_primary().unwrap();
+    let profile = profiles.get(*difficulty);
+    perception.record(
+        time_control.delta_seconds(),
+        ball_query
+            .iter()
+            .map(|(transform, velocity)| PerceivedBall {
+                position: transform.translation,
+                direction: velocity.direction,
+                speed: velocity.speed,
+            })
+            .collect(),
+    );
+
+    // Nowa wymiana odpoczywa, każde odbicie męczy i zmusza do ponownego odczytania lotu piłki
+    let new_rally = goal_events.iter().count() + serve_events.iter().count() > 0;
+    let hits = hit_events.iter().count();
+
+    for (ai_transform, collider, mut action_state, mut target, mut mind) in ai_query.iter_mut() {
+        if new_rally {
+            mind.rest();
+        }
+        for _ in 0..hits {
+            mind.tire(profile);
+        }
+
+        let seen = perception.sample(mind.reaction_delay(profile));
+        if new_rally || hits > 0 {
+            let ball_speed = seen.iter().map(|ball| ball.speed).fold(0., f32::max);
+            mind.rethink(profile, ball_speed, collider.height / 2.);
+        }
+
+        let balls: Vec<(Vec3, Vec2)> = seen
+            .iter()
+            .map(|ball| (ball.position, ball.direction))
+            .collect();
+        target.0 = difficulty
+            .target_y(ai_transform.translation, &balls, window.height() / 2.)
+            .map(|target_y| target_y + mind.error + mind.edge);
+
+        if let Some(target_y) = target.0 {
+            if ai_transform.translation.y > target_y + profile.deadzone {
                 action_state.press(PlayerInput::Down);
-            } else if ai_transform.translation.y < avg {
+            } else if ai_transform.translation.y < target_y - profile.deadzone {
                 action_state.press(PlayerInput::Up);
             }
         }
@@ -111,13 +286,19 @@ fn player_ai_system(
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<PlayerInput>::default())
+            .init_resource::<AiDifficulty>()
+            .init_resource::<AiProfiles>()
+            .init_resource::<KeyBindings>()
+            .add_startup_system(load_ai_profiles_system)
             .add_system_set(
-                SystemSet::on_update(GameState::InGame)
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
                     .with_system(player_ai_system)
                     .before("pm")
             )
             .add_system_set(
-                SystemSet::on_update(GameState::InGame)
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
                     .with_system(player_movement_system)
                     .label("pm"),
             );
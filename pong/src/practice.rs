@@ -0,0 +1,216 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use profiles::Profiles;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collisions::{BallHitEvent, ObstacleHitEvent, PlayerCollider},
+    profile::high_score_board,
+    time_control::{simulation_run_criteria, TimeControl},
+    GameMode, GameState, MatchEntity, MatchSetup, Score,
+};
+
+const SESSION_SECONDS: f32 = 60.;
+const TARGET_SIZE: f32 = 30.;
+const TARGET_EDGE_MARGIN: f32 = 60.;
+
+pub struct PracticePlugin;
+
+#[derive(Component)]
+pub struct Target;
+
+// Zapisywana razem z meczem, żeby wznowiona sesja miała ten sam czas i cele
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct PracticeSession {
+    time_left: f32,
+    // Cel liczy się tylko wtedy, gdy piłka od ostatniego trafienia odbiła się od paletki
+    armed: bool,
+    // Rekord sprzed sesji; nowy zapisuje profil po zakończeniu meczu
+    previous_best: Option<i64>,
+    new_best: bool,
+    rng: u64,
+}
+
+impl PracticeSession {
+    fn new(previous_best: Option<i64>) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            time_left: SESSION_SECONDS,
+            armed: false,
+            previous_best,
+            new_best: false,
+            rng: seed,
+        }
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.rng = self
+            .rng
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+fn spawn_target(commands: &mut Commands, session: &mut PracticeSession, window: &Window) {
+    let half_width = window.width() / 2. - TARGET_EDGE_MARGIN;
+    let half_height = window.height() / 2. - TARGET_EDGE_MARGIN;
+    let translation = Vec3::new(
+        session.range(0., half_width),
+        session.range(-half_height, half_height),
+        0.,
+    );
+    spawn_target_at(commands, translation);
+}
+
+pub fn spawn_target_at(commands: &mut Commands, translation: Vec3) {
+    commands.spawn((
+        MatchEntity,
+        Target,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(TARGET_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        PlayerCollider {
+            width: TARGET_SIZE,
+            height: TARGET_SIZE,
+        },
+    ));
+}
+
+fn personal_best(profiles: &Profiles, mode: GameMode) -> Option<i64> {
+    high_score_board(mode).and_then(|board| profiles.active().best(board))
+}
+
+fn setup_practice(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    setup: Res<MatchSetup>,
+    profiles: Res<Profiles>,
+) {
+    commands.remove_resource::<PracticeSession>();
+    if !setup.mode.is_practice() {
+        return;
+    }
+    let mut session = PracticeSession::new(personal_best(&profiles, setup.mode));
+    if setup.mode == GameMode::TargetDrill {
+        spawn_target(&mut commands, &mut session, windows.get_primary().unwrap());
+    }
+    commands.insert_resource(session);
+}
+
+fn close_practice(mut commands: Commands) {
+    commands.remove_resource::<PracticeSession>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn practice_hit_system(
+    mut commands: Commands,
+    mut hit_events: EventReader<BallHitEvent>,
+    mut obstacle_events: EventReader<ObstacleHitEvent>,
+    session: Option<ResMut<PracticeSession>>,
+    setup: Res<MatchSetup>,
+    windows: Res<Windows>,
+    mut score_query: Query<&mut Score>,
+    targets: Query<(), With<Target>>,
+) {
+    let Some(mut session) = session else {
+        return;
+    };
+    let Ok(mut score) = score_query.get_single_mut() else {
+        return;
+    };
+
+    for _ in hit_events.iter() {
+        session.armed = true;
+        if setup.mode == GameMode::WallPractice {
+            score.player1_score += 1;
+        }
+    }
+    for hit in obstacle_events.iter() {
+        if targets.get(hit.obstacle).is_ok() && session.armed {
+            session.armed = false;
+            score.player1_score += 1;
+            commands.entity(hit.obstacle).despawn();
+            spawn_target(&mut commands, &mut session, windows.get_primary().unwrap());
+        }
+    }
+}
+
+fn practice_timer_system(
+    session: Option<ResMut<PracticeSession>>,
+    mut app_state: ResMut<State<GameState>>,
+    time_control: Res<TimeControl>,
+    score_query: Query<&Score>,
+) {
+    let Some(mut session) = session else {
+        return;
+    };
+    if session.time_left <= 0. {
+        return;
+    }
+    session.time_left -= time_control.delta_seconds();
+    if session.time_left > 0. {
+        return;
+    }
+
+    session.time_left = 0.;
+    let points = score_query
+        .get_single()
+        .map_or(0, |score| score.player1_score);
+    session.new_best = points as i64 > session.previous_best.unwrap_or_default();
+    let _ = app_state.set(GameState::MatchOver);
+}
+
+fn draw_practice_hud(
+    mut egui_context: ResMut<EguiContext>,
+    session: Option<Res<PracticeSession>>,
+    profiles: Res<Profiles>,
+    setup: Res<MatchSetup>,
+) {
+    let Some(session) = session else {
+        return;
+    };
+    let best = personal_best(&profiles, setup.mode).unwrap_or_default();
+
+    egui::Window::new("Practice")
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10., 10.))
+        .title_bar(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("Time left: {:.1}s", session.time_left));
+            ui.label(format!("Personal best: {best}"));
+            if session.new_best {
+                ui.strong("New personal best!");
+            }
+        });
+}
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_practice))
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(close_practice))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame).with_system(practice_hit_system),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(simulation_run_criteria)
+                    .with_system(practice_timer_system),
+            )
+            .add_system(draw_practice_hud);
+    }
+}
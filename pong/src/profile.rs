@@ -0,0 +1,310 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use profiles::{Profile, Profiles, ScoreOrder};
+use serde_json::Value;
+
+use crate::{
+    ball::GoalEvent,
+    collisions::BallHitEvent,
+    player::{Controller, KeyBindings, PADDLE_KEYS},
+    theme::{PaletteId, VisualSettings, MAX_FONT_SCALE, MIN_FONT_SCALE},
+    GameMode, GameState, MatchSetup, Score,
+};
+
+const GAME: &str = "pong";
+
+pub struct PongProfilePlugin;
+
+#[derive(Resource, Default)]
+struct ProfileMenu {
+    new_name: String,
+    // Paletka, dla której czekamy na klawisze, i już wciśnięty klawisz "w górę"
+    capture: Option<(usize, Option<KeyCode>)>,
+}
+
+pub fn high_score_board(mode: GameMode) -> Option<&'static str> {
+    match mode {
+        GameMode::Breakout => Some("pong.breakout"),
+        GameMode::WallPractice => Some("pong.wall_practice"),
+        GameMode::TargetDrill => Some("pong.target_drill"),
+        _ => None,
+    }
+}
+
+fn key_action(slot: usize, action: &str) -> String {
+    format!("{GAME}.paddle{}.{action}", slot + 1)
+}
+
+fn profile_key(profile: &Profile, action: &str) -> Option<KeyCode> {
+    let name = profile.key_bindings.get(action)?;
+    serde_json::from_value(Value::String(name.clone())).ok()
+}
+
+fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+fn profile_bindings(profile: &Profile) -> KeyBindings {
+    let mut bindings = KeyBindings::default();
+    for (slot, (up, down)) in bindings.paddles.iter_mut().enumerate() {
+        if let Some(key) = profile_key(profile, &key_action(slot, "up")) {
+            *up = key;
+        }
+        if let Some(key) = profile_key(profile, &key_action(slot, "down")) {
+            *down = key;
+        }
+    }
+    bindings
+}
+
+fn profile_visuals(profile: &Profile) -> VisualSettings {
+    let palette = profile
+        .theme
+        .as_ref()
+        .and_then(|name| serde_json::from_value(Value::String(name.clone())).ok())
+        .unwrap_or(PaletteId::Classic);
+    VisualSettings {
+        palette,
+        font_scale: profile.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE),
+    }
+}
+
+// Po zmianie aktywnego profilu wczytujemy jego motyw i klawisze
+fn apply_profile_system(
+    profiles: Res<Profiles>,
+    mut applied: Local<Option<usize>>,
+    mut visuals: ResMut<VisualSettings>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    if *applied == Some(profiles.active) {
+        return;
+    }
+    *applied = Some(profiles.active);
+
+    let profile = profiles.active();
+    let new_visuals = profile_visuals(profile);
+    if new_visuals.palette != visuals.palette || new_visuals.font_scale != visuals.font_scale {
+        *visuals = new_visuals;
+    }
+    let new_bindings = profile_bindings(profile);
+    if new_bindings != *bindings {
+        *bindings = new_bindings;
+    }
+}
+
+fn store_visuals_system(visuals: Res<VisualSettings>, mut profiles: ResMut<Profiles>) {
+    if !visuals.is_changed() || visuals.is_added() {
+        return;
+    }
+    let theme = Some(format!("{:?}", visuals.palette));
+    let profile = profiles.active();
+    if profile.theme == theme && profile.font_scale == visuals.font_scale {
+        return;
+    }
+    let profile = profiles.active_mut();
+    profile.theme = theme;
+    profile.font_scale = visuals.font_scale;
+    profiles.save_or_log();
+}
+
+fn store_bindings_system(
+    bindings: Res<KeyBindings>,
+    mut previous: Local<KeyBindings>,
+    mut profiles: ResMut<Profiles>,
+    mut setup: ResMut<MatchSetup>,
+) {
+    if !bindings.is_changed() || *previous == *bindings {
+        return;
+    }
+
+    // Paletki ustawione w menu na dany zestaw klawiszy dostają jego nowe klawisze
+    for controller in setup.controllers.iter_mut() {
+        if let Controller::Keys { up, down } = *controller {
+            if let Some(index) = previous.paddles.iter().position(|&keys| keys == (up, down)) {
+                *controller = bindings.controller(index);
+            }
+        }
+    }
+    *previous = *bindings;
+
+    let profile = profiles.active_mut();
+    let mut changed = false;
+    for (slot, &(up, down)) in bindings.paddles.iter().enumerate() {
+        for (action, key) in [("up", up), ("down", down)] {
+            let action = key_action(slot, action);
+            if profile_key(profile, &action) == Some(key) {
+                continue;
+            }
+            profile.key_bindings.insert(action, key_name(key));
+            changed = true;
+        }
+    }
+    if changed {
+        profiles.save_or_log();
+    }
+}
+
+fn match_stats_system(
+    mut goal_events: EventReader<GoalEvent>,
+    mut hit_events: EventReader<BallHitEvent>,
+    mut profiles: ResMut<Profiles>,
+) {
+    let goals = goal_events.iter().count() as u64;
+    let hits = hit_events.iter().count() as u64;
+    if goals == 0 && hits == 0 {
+        return;
+    }
+    let profile = profiles.active_mut();
+    profile.count(GAME, "goals", goals);
+    profile.count(GAME, "paddle_hits", hits);
+}
+
+fn record_match_system(
+    mut profiles: ResMut<Profiles>,
+    setup: Res<MatchSetup>,
+    score_query: Query<&Score>,
+) {
+    let profile = profiles.active_mut();
+    profile.count(GAME, "matches", 1);
+    if let (Some(board), Ok(score)) = (high_score_board(setup.mode), score_query.get_single()) {
+        profile.record_score(
+            board,
+            score.player1_score as i64,
+            ScoreOrder::HigherIsBetter,
+        );
+    }
+    profiles.save_or_log();
+}
+
+fn capture_keys_system(
+    input: Res<Input<KeyCode>>,
+    mut menu: ResMut<ProfileMenu>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    let Some((slot, up)) = menu.capture else {
+        return;
+    };
+    let Some(&key) = input.get_just_pressed().next() else {
+        return;
+    };
+    if key == KeyCode::Escape {
+        menu.capture = None;
+        return;
+    }
+    match up {
+        None => menu.capture = Some((slot, Some(key))),
+        Some(up) => {
+            bindings.paddles[slot] = (up, key);
+            menu.capture = None;
+        }
+    }
+}
+
+fn draw_profile_menu(
+    mut egui_context: ResMut<EguiContext>,
+    app_state: Res<State<GameState>>,
+    mut profiles: ResMut<Profiles>,
+    mut menu: ResMut<ProfileMenu>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    if app_state.current() != &GameState::MainMenu {
+        return;
+    }
+
+    egui::Window::new("Profile")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(10., 10.))
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let mut active = profiles.active;
+            egui::ComboBox::from_label("Player")
+                .selected_text(profiles.active().name.clone())
+                .show_ui(ui, |ui| {
+                    for (index, profile) in profiles.profiles.iter().enumerate() {
+                        ui.selectable_value(&mut active, index, profile.name.as_str());
+                    }
+                });
+            if active != profiles.active {
+                profiles.select(active);
+                profiles.save_or_log();
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut menu.new_name);
+                let name = menu.new_name.trim().to_string();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Add"))
+                    .clicked()
+                {
+                    let index = profiles.add(name);
+                    profiles.select(index);
+                    profiles.save_or_log();
+                    menu.new_name.clear();
+                }
+            });
+            ui.separator();
+
+            ui.heading("Controls");
+            for slot in 0..PADDLE_KEYS.len() {
+                let (up, down) = bindings.paddles[slot];
+                let label = match menu.capture {
+                    Some((capture_slot, None)) if capture_slot == slot => {
+                        "press up key...".to_string()
+                    }
+                    Some((capture_slot, Some(_))) if capture_slot == slot => {
+                        "press down key...".to_string()
+                    }
+                    _ => format!("{up:?}/{down:?}"),
+                };
+                ui.horizontal(|ui| {
+                    ui.label(format!("Paddle {}", slot + 1));
+                    if ui.button(label).clicked() {
+                        menu.capture = Some((slot, None));
+                    }
+                });
+            }
+            if ui.button("Default keys").clicked() {
+                *bindings = KeyBindings::default();
+            }
+            ui.separator();
+
+            ui.heading("Records");
+            let profile = profiles.active();
+            if let Some(stats) = profile.stats.get(GAME) {
+                let counter = |name: &str| stats.counters.get(name).copied().unwrap_or_default();
+                ui.label(format!(
+                    "Sessions: {}, played {:.0} min",
+                    stats.sessions,
+                    stats.play_seconds / 60.
+                ));
+                ui.label(format!(
+                    "Matches: {}, goals: {}, returns: {}",
+                    counter("matches"),
+                    counter("goals"),
+                    counter("paddle_hits")
+                ));
+            }
+            for mode in GameMode::ALL {
+                let Some(board) = high_score_board(mode) else {
+                    continue;
+                };
+                let best = profile
+                    .best(board)
+                    .map_or("-".to_string(), |best| best.to_string());
+                ui.label(format!("{}: {best}", mode.name()));
+            }
+        });
+}
+
+impl Plugin for PongProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProfileMenu>()
+            .add_system(apply_profile_system)
+            .add_system(store_visuals_system.after(apply_profile_system))
+            .add_system(store_bindings_system.after(apply_profile_system))
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(match_stats_system))
+            .add_system_set(
+                SystemSet::on_enter(GameState::MatchOver).with_system(record_match_system),
+            )
+            .add_system(capture_keys_system)
+            .add_system(draw_profile_menu);
+    }
+}
@@ -0,0 +1,234 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{spawn_ball, Ball, NextServe},
+    breakout::{spawn_brick, Brick},
+    player::{AiDifficulty, Player, Side},
+    practice::{spawn_target_at, PracticeSession, Target},
+    GameState, MatchRules, MatchSetup, Score, Velocity,
+};
+
+const SAVE_PATH: &str = "pong_save.json";
+
+pub struct SavePlugin;
+
+pub struct SaveMatchEvent;
+
+#[derive(Resource, Default)]
+pub struct SaveStatus {
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BallSnapshot {
+    ball: Ball,
+    translation: Vec3,
+    velocity: Velocity,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaddleSnapshot {
+    id: usize,
+    translation: Vec3,
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+struct MatchSnapshot {
+    setup: MatchSetup,
+    rules: MatchRules,
+    ai_difficulty: AiDifficulty,
+    score: [i32; 2],
+    balls: Vec<BallSnapshot>,
+    paddles: Vec<PaddleSnapshot>,
+    next_serve: Side,
+    // Stan trybów: cegły Breakoutu oraz sesja treningowa z celami
+    bricks: Vec<Vec3>,
+    practice: Option<PracticeSession>,
+    targets: Vec<Vec3>,
+    state: GameState,
+}
+
+impl MatchSnapshot {
+    fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_match_system(
+    mut save_events: EventReader<SaveMatchEvent>,
+    mut status: ResMut<SaveStatus>,
+    app_state: Res<State<GameState>>,
+    setup: Res<MatchSetup>,
+    rules: Res<MatchRules>,
+    ai_difficulty: Res<AiDifficulty>,
+    score_query: Query<&Score>,
+    ball_query: Query<(&Ball, &Transform, &Velocity)>,
+    paddle_query: Query<(&Player, &Transform)>,
+    next_serve: Res<NextServe>,
+    practice: Option<Res<PracticeSession>>,
+    brick_query: Query<&Transform, With<Brick>>,
+    target_query: Query<&Transform, With<Target>>,
+) {
+    if save_events.iter().next().is_none() {
+        return;
+    }
+    let score = score_query.get_single().unwrap();
+
+    let snapshot = MatchSnapshot {
+        setup: setup.clone(),
+        rules: rules.clone(),
+        ai_difficulty: *ai_difficulty,
+        score: [score.player1_score, score.player2_score],
+        balls: ball_query
+            .iter()
+            .map(|(ball, transform, velocity)| BallSnapshot {
+                ball: ball.clone(),
+                translation: transform.translation,
+                velocity: velocity.clone(),
+            })
+            .collect(),
+        paddles: paddle_query
+            .iter()
+            .map(|(player, transform)| PaddleSnapshot {
+                id: player.id,
+                translation: transform.translation,
+            })
+            .collect(),
+        next_serve: next_serve.0,
+        bricks: brick_query
+            .iter()
+            .map(|transform| transform.translation)
+            .collect(),
+        practice: practice.map(|session| session.clone()),
+        targets: target_query
+            .iter()
+            .map(|transform| transform.translation)
+            .collect(),
+        state: app_state.current().clone(),
+    };
+
+    status.message = Some(match snapshot.save(SAVE_PATH) {
+        Ok(()) => format!("Match saved to {SAVE_PATH}"),
+        Err(err) => format!("Failed to save match: {err}"),
+    });
+}
+
+fn load_snapshot_system(mut commands: Commands) {
+    if !Path::new(SAVE_PATH).exists() {
+        return;
+    }
+    match MatchSnapshot::load(SAVE_PATH) {
+        // Zapis powstaje tylko w trakcie meczu albo z menu pauzy
+        Ok(snapshot) if !matches!(snapshot.state, GameState::InGame | GameState::Paused) => {
+            error!(
+                "Ignoring saved match with unexpected state {:?}",
+                snapshot.state
+            );
+        }
+        Ok(snapshot) => {
+            commands.insert_resource(snapshot.setup.clone());
+            commands.insert_resource(snapshot.rules.clone());
+            commands.insert_resource(snapshot.ai_difficulty);
+            commands.insert_resource(snapshot);
+        }
+        Err(err) => error!("Failed to load saved match: {err}"),
+    }
+}
+
+fn resume_match_system(
+    snapshot: Option<Res<MatchSnapshot>>,
+    mut app_state: ResMut<State<GameState>>,
+) {
+    if snapshot.is_some() {
+        app_state.replace(GameState::InGame).unwrap();
+    }
+}
+
+// Mecz jest odtwarzany dopiero, kiedy systemy wejścia do InGame stworzą paletki, piłkę
+// i obiekty trybu - te ostatnie są zastępowane zapisanymi
+#[allow(clippy::too_many_arguments)]
+fn restore_match_system(
+    mut commands: Commands,
+    snapshot: Option<Res<MatchSnapshot>>,
+    mut app_state: ResMut<State<GameState>>,
+    mut score_query: Query<&mut Score>,
+    mut paddle_query: Query<(&Player, &mut Transform)>,
+    ball_query: Query<Entity, With<Ball>>,
+    brick_query: Query<Entity, With<Brick>>,
+    target_query: Query<Entity, With<Target>>,
+) {
+    let Some(snapshot) = snapshot else {
+        return;
+    };
+    let Ok(mut score) = score_query.get_single_mut() else {
+        return;
+    };
+
+    score.player1_score = snapshot.score[0];
+    score.player2_score = snapshot.score[1];
+
+    for entity in ball_query
+        .iter()
+        .chain(brick_query.iter())
+        .chain(target_query.iter())
+    {
+        commands.entity(entity).despawn();
+    }
+    for saved in &snapshot.balls {
+        let ball = spawn_ball(
+            &mut commands,
+            saved.translation,
+            saved.velocity.direction,
+            saved.velocity.speed,
+        );
+        commands.entity(ball).insert(saved.ball.clone());
+    }
+    for translation in &snapshot.bricks {
+        spawn_brick(&mut commands, *translation);
+    }
+    for translation in &snapshot.targets {
+        spawn_target_at(&mut commands, *translation);
+    }
+    if let Some(session) = &snapshot.practice {
+        commands.insert_resource(session.clone());
+    }
+    commands.insert_resource(NextServe(snapshot.next_serve));
+
+    for (player, mut transform) in paddle_query.iter_mut() {
+        if let Some(saved) = snapshot.paddles.iter().find(|saved| saved.id == player.id) {
+            transform.translation = saved.translation;
+        }
+    }
+
+    if snapshot.state == GameState::Paused {
+        app_state.push(GameState::Paused).unwrap();
+    }
+
+    commands.remove_resource::<MatchSnapshot>();
+    if let Err(err) = fs::remove_file(SAVE_PATH) {
+        error!("Failed to remove restored save: {err}");
+    }
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveMatchEvent>()
+            .init_resource::<SaveStatus>()
+            .add_startup_system(load_snapshot_system)
+            .add_system(save_match_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(resume_match_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame).with_system(restore_match_system),
+            );
+    }
+}
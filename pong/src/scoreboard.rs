@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+
+use crate::{
+    ball::{GoalEvent, NextServe},
+    player::Side,
+    theme::{TextRole, ThemedText, VisualSettings},
+    tournament::Tournament,
+    GameMode, MatchRules, MatchSetup, Score,
+};
+
+const SCORE_FONT_SIZE: f32 = 60.;
+const STATUS_FONT_SIZE: f32 = 24.;
+const PULSE_SECONDS: f32 = 0.6;
+const PULSE_SCALE: f32 = 0.6;
+
+// Indeksy sekcji z wynikami drużyn w tekście tablicy
+const LEFT: usize = 0;
+const RIGHT: usize = 2;
+
+pub struct ScoreboardPlugin;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component)]
+struct StatusRow;
+
+fn team_names(mode: GameMode, tournament: Option<&Tournament>) -> (String, String) {
+    if let Some(tournament) = tournament {
+        if let Some(fixture) = tournament.current_fixture() {
+            return (
+                tournament.slot_name(fixture.left),
+                tournament.slot_name(fixture.right),
+            );
+        }
+    }
+    let (left, right) = match mode {
+        GameMode::OneVsOne => ("Player 1", "Player 2"),
+        GameMode::TwoVsTwo => ("Left team", "Right team"),
+        GameMode::VsAi => ("Player", "AI"),
+        GameMode::Breakout => ("Bricks", "Balls lost"),
+        GameMode::WallPractice => ("Returns", "Misses"),
+        GameMode::TargetDrill => ("Targets", "Misses"),
+    };
+    (left.to_string(), right.to_string())
+}
+
+fn status_line(score: &Score, mode: GameMode, limit: i32, next_serve: Side) -> String {
+    let mut status = Vec::new();
+    if mode == GameMode::Breakout {
+        if score.player2_score == limit - 1 {
+            status.push("LAST BALL");
+        }
+    } else if !mode.is_practice() {
+        status.push(match next_serve {
+            Side::Left => "< serve",
+            Side::Right => "serve >",
+        });
+        if score.player1_score == limit - 1 || score.player2_score == limit - 1 {
+            status.push("MATCH POINT");
+        }
+    }
+    format!("   {}   ", status.join(" | "))
+}
+
+fn text_style(asset_server: &AssetServer, font_size: f32) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraCode-Regular.ttf"),
+        font_size,
+        color: Color::WHITE,
+    }
+}
+
+fn centered_row(top: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Undefined),
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(top),
+                ..default()
+            },
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn setup_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let score_style = text_style(&asset_server, SCORE_FONT_SIZE);
+    let status_style = text_style(&asset_server, STATUS_FONT_SIZE);
+
+    commands.spawn(centered_row(0.)).with_children(|parent| {
+        parent.spawn((
+            ScoreText,
+            ThemedText {
+                font_size: SCORE_FONT_SIZE,
+                roles: &[
+                    TextRole::Team(Side::Left),
+                    TextRole::Text,
+                    TextRole::Team(Side::Right),
+                ],
+            },
+            TextBundle::from_sections([
+                TextSection::new("", score_style.clone()),
+                TextSection::new("", score_style.clone()),
+                TextSection::new("", score_style),
+            ]),
+        ));
+    });
+    commands
+        .spawn((StatusRow, centered_row(SCORE_FONT_SIZE + 10.)))
+        .with_children(|parent| {
+            parent.spawn((
+                StatusText,
+                ThemedText {
+                    font_size: STATUS_FONT_SIZE,
+                    roles: &[
+                        TextRole::Team(Side::Left),
+                        TextRole::Accent,
+                        TextRole::Team(Side::Right),
+                    ],
+                },
+                TextBundle::from_sections([
+                    TextSection::new("", status_style.clone()),
+                    TextSection::new("", status_style.clone()),
+                    TextSection::new("", status_style),
+                ]),
+            ));
+        });
+}
+
+// Tablica jest przepisywana tylko gdy zmieni się wynik albo strona serwująca
+#[allow(clippy::too_many_arguments)]
+fn update_scoreboard_system(
+    score_query: Query<&Score>,
+    changed_query: Query<(), Changed<Score>>,
+    removed: RemovedComponents<Score>,
+    next_serve: Res<NextServe>,
+    setup: Res<MatchSetup>,
+    rules: Res<MatchRules>,
+    tournament: Option<Res<Tournament>>,
+    mut score_text_query: Query<&mut Text, (With<ScoreText>, Without<StatusText>)>,
+    mut status_text_query: Query<&mut Text, (With<StatusText>, Without<ScoreText>)>,
+) {
+    if changed_query.is_empty() && !next_serve.is_changed() && removed.iter().next().is_none() {
+        return;
+    }
+
+    let (score_values, status_values) = match score_query.get_single() {
+        Ok(score) => {
+            let (left_name, right_name) = team_names(setup.mode, tournament.as_deref());
+            (
+                [
+                    score.player1_score.to_string(),
+                    " - ".to_string(),
+                    score.player2_score.to_string(),
+                ],
+                [
+                    left_name,
+                    status_line(score, setup.mode, rules.score_limit, next_serve.0),
+                    right_name,
+                ],
+            )
+        }
+        Err(_) => Default::default(),
+    };
+
+    for mut text in score_text_query.iter_mut() {
+        for (section, value) in text.sections.iter_mut().zip(score_values.iter()) {
+            section.value = value.clone();
+        }
+    }
+    for mut text in status_text_query.iter_mut() {
+        for (section, value) in text.sections.iter_mut().zip(status_values.iter()) {
+            section.value = value.clone();
+        }
+    }
+}
+
+// Wiersz statusu przesuwa się razem ze skalą czcionki wyniku
+fn layout_scoreboard_system(
+    settings: Res<VisualSettings>,
+    mut row_query: Query<&mut Style, With<StatusRow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut style in row_query.iter_mut() {
+        style.position.top = Val::Px(SCORE_FONT_SIZE * settings.font_scale + 10.);
+    }
+}
+
+fn goal_animation_system(
+    mut goal_events: EventReader<GoalEvent>,
+    mut pulse: Local<Option<(Side, Timer)>>,
+    time: Res<Time>,
+    settings: Res<VisualSettings>,
+    mut text_query: Query<&mut Text, With<ScoreText>>,
+) {
+    if let Some(goal) = goal_events.iter().last() {
+        *pulse = Some((
+            goal.scoring_side,
+            Timer::from_seconds(PULSE_SECONDS, TimerMode::Once),
+        ));
+    }
+    let Some((side, timer)) = pulse.as_mut() else {
+        return;
+    };
+
+    timer.tick(time.delta());
+    let remaining = 1. - timer.percent();
+    let font_size =
+        SCORE_FONT_SIZE * settings.font_scale * (1. + PULSE_SCALE * remaining * remaining);
+    let index = match side {
+        Side::Left => LEFT,
+        Side::Right => RIGHT,
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[index].style.font_size = font_size;
+    }
+
+    if timer.finished() {
+        *pulse = None;
+    }
+}
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_scoreboard)
+            .add_system(update_scoreboard_system)
+            .add_system(layout_scoreboard_system)
+            .add_system(goal_animation_system);
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState, InputPlugin},
+    prelude::*,
+    window::WindowId,
+};
+use bevy_kira_audio::prelude::*;
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+use crate::{
+    apply_velocity,
+    ball::BallPlugin,
+    collisions::{BallCollider, BroadphasePhase, CollisionPlugin, PlayerCollider},
+    player::{Controller, KeyBindings, Player, PlayerPlugin},
+    setup_players, setup_score,
+    time_control::{simulation_run_criteria, TimeControl, TimeControlPlugin},
+    BallSound, GameMode, GameState, MatchRules, MatchSetup, Score, Velocity,
+};
+
+const ARENA_WIDTH: f32 = 1200.;
+const ARENA_HEIGHT: f32 = 600.;
+const STEPS: usize = 600;
+const EPSILON: f32 = 1e-3;
+
+// Ten sam generator liniowy co w benchmarku - cały scenariusz wynika z jednego ziarna,
+// więc proptest zmniejsza przy porażce tylko ziarno
+struct SimRng(u64);
+
+impl SimRng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+fn headless_app(mode: GameMode) -> App {
+    let mut windows = Windows::default();
+    windows.add(Window::new(
+        WindowId::primary(),
+        &WindowDescriptor {
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+            ..default()
+        },
+        ARENA_WIDTH as u32,
+        ARENA_HEIGHT as u32,
+        1.,
+        None,
+        None,
+    ));
+
+    // Symulacja idzie tylko krokami, więc wynik nie zależy od zegara
+    let mut time_control = TimeControl::default();
+    time_control.frozen = true;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(InputPlugin)
+        .insert_resource(windows)
+        .init_resource::<Audio>()
+        .insert_resource(BallSound {
+            audio_handle: Handle::default(),
+        })
+        .insert_resource(time_control)
+        .insert_resource(MatchSetup::for_mode(mode, &KeyBindings::default()))
+        .insert_resource(MatchRules {
+            speed: mode.speed_curve(),
+            ..default()
+        })
+        .add_state(GameState::InGame)
+        .add_system_set(
+            SystemSet::on_enter(GameState::InGame)
+                .with_system(setup_score)
+                .with_system(setup_players),
+        )
+        .add_plugin(BallPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(TimeControlPlugin)
+        .add_system_set(
+            SystemSet::new()
+                .with_run_criteria(simulation_run_criteria)
+                .with_system(apply_velocity.before(BroadphasePhase)),
+        );
+    app.update();
+    app
+}
+
+fn send_key(app: &mut App, key: KeyCode, state: ButtonState) {
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key),
+        state,
+    });
+}
+
+fn score(app: &mut App) -> (i32, i32) {
+    let score = app.world.query::<&Score>().single(&app.world);
+    (score.player1_score, score.player2_score)
+}
+
+fn randomize_start(app: &mut App, rng: &mut SimRng) {
+    let max_speed = app.world.resource::<MatchRules>().speed.max_speed;
+    for (mut transform, mut velocity, collider) in app
+        .world
+        .query::<(&mut Transform, &mut Velocity, &BallCollider)>()
+        .iter_mut(&mut app.world)
+    {
+        let half_height = ARENA_HEIGHT / 2. - collider.radius;
+        transform.translation = Vec3::new(
+            rng.range(-ARENA_WIDTH / 4., ARENA_WIDTH / 4.),
+            rng.range(-half_height, half_height),
+            0.,
+        );
+        let angle = rng.range(0., std::f32::consts::TAU);
+        velocity.direction = Vec2::new(angle.cos(), angle.sin());
+        velocity.speed = rng.range(100., max_speed);
+    }
+
+    for (mut transform, _) in app
+        .world
+        .query::<(&mut Transform, &Player)>()
+        .iter_mut(&mut app.world)
+    {
+        transform.translation.y = rng.range(-ARENA_HEIGHT / 2., ARENA_HEIGHT / 2.);
+    }
+}
+
+fn check_invariants(app: &mut App, seed: u64, step: usize) -> Result<(), TestCaseError> {
+    let max_speed = app.world.resource::<MatchRules>().speed.max_speed;
+    let paddles: Vec<(Vec2, Vec2)> = app
+        .world
+        .query::<(&Transform, &PlayerCollider, &Player)>()
+        .iter(&app.world)
+        .map(|(transform, collider, _)| {
+            let center = transform.translation.truncate();
+            let half_size = Vec2::new(collider.width, collider.height) / 2.;
+            (center - half_size, center + half_size)
+        })
+        .collect();
+
+    for (transform, collider, velocity) in app
+        .world
+        .query::<(&Transform, &BallCollider, &Velocity)>()
+        .iter(&app.world)
+    {
+        let center = transform.translation.truncate();
+        let (min, max) = (center - collider.radius, center + collider.radius);
+
+        prop_assert!(
+            min.y >= -ARENA_HEIGHT / 2. - EPSILON && max.y <= ARENA_HEIGHT / 2. + EPSILON,
+            "seed {}: ball left the arena at step {}: {:?}",
+            seed,
+            step,
+            center
+        );
+        prop_assert!(
+            velocity.speed <= max_speed + EPSILON,
+            "seed {}: ball speed {} exceeds the cap at step {}",
+            seed,
+            velocity.speed,
+            step
+        );
+        for &(p_min, p_max) in paddles.iter() {
+            let inside = min.x < p_max.x - EPSILON
+                && max.x > p_min.x + EPSILON
+                && min.y < p_max.y - EPSILON
+                && max.y > p_min.y + EPSILON;
+            prop_assert!(
+                !inside,
+                "seed {}: ball {:?} ended up inside a paddle at step {}",
+                seed,
+                center,
+                step
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_scenario(seed: u64, mode: GameMode) -> Result<(), TestCaseError> {
+    let mut rng = SimRng(seed);
+    let mut app = headless_app(mode);
+    randomize_start(&mut app, &mut rng);
+
+    let keys: Vec<(KeyCode, KeyCode)> = app
+        .world
+        .resource::<MatchSetup>()
+        .controllers
+        .iter()
+        .filter_map(|controller| match *controller {
+            Controller::Keys { up, down } => Some((up, down)),
+            Controller::Ai => None,
+        })
+        .collect();
+
+    // Wynik liczymy niezależnie od gry - z pozycji piłki względem linii bramek
+    let half_width = ARENA_WIDTH / 2.;
+    let mut expected = (0, 0);
+    let mut in_play = true;
+    let mut serve_at = None;
+
+    for step in 0..STEPS {
+        for &(up, down) in keys.iter() {
+            send_key(&mut app, up, ButtonState::Released);
+            send_key(&mut app, down, ButtonState::Released);
+            let roll = rng.next_f32();
+            if roll < 0.4 {
+                send_key(&mut app, up, ButtonState::Pressed);
+            } else if roll < 0.8 {
+                send_key(&mut app, down, ButtonState::Pressed);
+            }
+        }
+        let serve = serve_at == Some(step);
+        send_key(
+            &mut app,
+            KeyCode::R,
+            if serve {
+                ButtonState::Pressed
+            } else {
+                ButtonState::Released
+            },
+        );
+
+        app.world.resource_mut::<TimeControl>().request_step();
+        app.update();
+        in_play |= serve;
+
+        let mut scored = (0, 0);
+        if in_play {
+            for (transform, _) in app
+                .world
+                .query::<(&Transform, &BallCollider)>()
+                .iter(&app.world)
+            {
+                if transform.translation.x > half_width {
+                    scored.0 += 1;
+                } else if transform.translation.x < -half_width {
+                    scored.1 += 1;
+                }
+            }
+        }
+        if scored != (0, 0) {
+            expected = (expected.0 + scored.0, expected.1 + scored.1);
+            in_play = false;
+            // Serwujemy z odstępem, żeby gra zdążyła zaliczyć punkt przed resetem piłki
+            serve_at = Some(step + 2);
+        }
+
+        // Gra może zauważyć piłkę za linią klatkę później, ale nigdy wcześniej
+        let (left, right) = score(&mut app);
+        let (lag_left, lag_right) = (expected.0 - left, expected.1 - right);
+        prop_assert!(
+            (0..=scored.0).contains(&lag_left) && (0..=scored.1).contains(&lag_right),
+            "seed {}: score {:?} does not match ball positions, expected {:?} at step {}",
+            seed,
+            (left, right),
+            expected,
+            step
+        );
+
+        check_invariants(&mut app, seed, step)?;
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn one_vs_one_invariants(seed in any::<u64>()) {
+        run_scenario(seed, GameMode::OneVsOne)?;
+    }
+
+    #[test]
+    fn two_vs_two_invariants(seed in any::<u64>()) {
+        run_scenario(seed, GameMode::TwoVsTwo)?;
+    }
+
+    #[test]
+    fn breakout_invariants(seed in any::<u64>()) {
+        run_scenario(seed, GameMode::Breakout)?;
+    }
+}
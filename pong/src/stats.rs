@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::Serialize;
+
+use crate::{
+    ball::{Ball, GoalEvent, ServeEvent},
+    collisions::BallHitEvent,
+    player::{AiDifficulty, Controller, Player, Side},
+    GameState, Score, Velocity,
+};
+
+const STATS_DIR: &str = "stats";
+
+pub struct StatsPlugin;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerKind {
+    Human,
+    Ai,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PaddleStats {
+    pub id: usize,
+    pub side: Side,
+    pub controller: ControllerKind,
+    pub hits: u32,
+    pub goals: u32,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct ControllerSummary {
+    pub paddles: u32,
+    pub hits: u32,
+    pub goals: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MatchReport {
+    pub score: [i32; 2],
+    pub ai_difficulty: AiDifficulty,
+    pub rallies: Vec<u32>,
+    pub longest_rally: u32,
+    pub average_rally: f32,
+    pub top_ball_speed: f32,
+    pub paddles: Vec<PaddleStats>,
+    pub ai: ControllerSummary,
+    pub human: ControllerSummary,
+}
+
+#[derive(Resource, Default)]
+pub struct MatchStats {
+    rallies: Vec<u32>,
+    top_ball_speed: f32,
+    paddles: HashMap<Entity, PaddleStats>,
+    current_rallies: HashMap<Entity, u32>,
+    last_hit: HashMap<Entity, Entity>,
+}
+
+impl MatchStats {
+    pub fn report(&self, score: &Score, ai_difficulty: AiDifficulty) -> MatchReport {
+        let mut paddles: Vec<PaddleStats> = self.paddles.values().cloned().collect();
+        paddles.sort_by_key(|paddle| paddle.id);
+
+        let mut ai = ControllerSummary::default();
+        let mut human = ControllerSummary::default();
+        for paddle in paddles.iter() {
+            let summary = match paddle.controller {
+                ControllerKind::Ai => &mut ai,
+                ControllerKind::Human => &mut human,
+            };
+            summary.paddles += 1;
+            summary.hits += paddle.hits;
+            summary.goals += paddle.goals;
+        }
+
+        let average_rally = if self.rallies.is_empty() {
+            0.
+        } else {
+            self.rallies.iter().sum::<u32>() as f32 / self.rallies.len() as f32
+        };
+
+        MatchReport {
+            score: [score.player1_score, score.player2_score],
+            ai_difficulty,
+            rallies: self.rallies.clone(),
+            longest_rally: self.rallies.iter().copied().max().unwrap_or(0),
+            average_rally,
+            top_ball_speed: self.top_ball_speed,
+            paddles,
+            ai,
+            human,
+        }
+    }
+}
+
+impl MatchReport {
+    pub fn export(&self) -> io::Result<PathBuf> {
+        fs::create_dir_all(STATS_DIR)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = PathBuf::from(STATS_DIR).join(format!("match-{timestamp}.json"));
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+fn track_paddles_system(
+    mut stats: ResMut<MatchStats>,
+    query: Query<(Entity, &Player, &Controller), Or<(Added<Player>, Changed<Controller>)>>,
+) {
+    for (entity, player, controller) in query.iter() {
+        let paddle = stats.paddles.entry(entity).or_insert(PaddleStats {
+            id: player.id,
+            side: player.side,
+            controller: ControllerKind::Human,
+            hits: 0,
+            goals: 0,
+        });
+        paddle.controller = match controller {
+            Controller::Ai => ControllerKind::Ai,
+            Controller::Keys { .. } => ControllerKind::Human,
+        };
+    }
+}
+
+fn reset_stats_system(mut stats: ResMut<MatchStats>) {
+    *stats = MatchStats::default();
+}
+
+fn track_ball_speed_system(mut stats: ResMut<MatchStats>, query: Query<&Velocity, With<Ball>>) {
+    for velocity in query.iter() {
+        stats.top_ball_speed = stats.top_ball_speed.max(velocity.speed);
+    }
+}
+
+fn record_hits_system(mut stats: ResMut<MatchStats>, mut hit_events: EventReader<BallHitEvent>) {
+    for hit in hit_events.iter() {
+        let Some(paddle) = stats.paddles.get_mut(&hit.paddle) else {
+            continue;
+        };
+        paddle.hits += 1;
+        *stats.current_rallies.entry(hit.ball).or_default() += 1;
+        stats.last_hit.insert(hit.ball, hit.paddle);
+    }
+}
+
+fn record_goals_system(mut stats: ResMut<MatchStats>, mut goal_events: EventReader<GoalEvent>) {
+    for goal in goal_events.iter() {
+        let rally = stats.current_rallies.remove(&goal.ball).unwrap_or_default();
+        stats.rallies.push(rally);
+
+        if let Some(last_hit) = stats.last_hit.remove(&goal.ball) {
+            if let Some(paddle) = stats.paddles.get_mut(&last_hit) {
+                if paddle.side == goal.scoring_side {
+                    paddle.goals += 1;
+                }
+            }
+        }
+    }
+}
+
+fn record_serves_system(mut stats: ResMut<MatchStats>, mut serve_events: EventReader<ServeEvent>) {
+    for serve in serve_events.iter() {
+        stats.current_rallies.remove(&serve.ball);
+        stats.last_hit.remove(&serve.ball);
+    }
+}
+
+fn draw_match_over_screen(
+    mut egui_context: ResMut<EguiContext>,
+    app_state: Res<State<GameState>>,
+    stats: Res<MatchStats>,
+    score_query: Query<&Score>,
+    ai_difficulty: Res<AiDifficulty>,
+    mut export_result: Local<Option<String>>,
+) {
+    if app_state.current() != &GameState::MatchOver {
+        *export_result = None;
+        return;
+    }
+    let report = stats.report(score_query.get_single().unwrap(), *ai_difficulty);
+
+    egui::Window::new("Match over")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0., 0.))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(format!("{} - {}", report.score[0], report.score[1]));
+            ui.separator();
+
+            egui::Grid::new("match_summary").show(ui, |ui| {
+                ui.label("AI difficulty");
+                ui.label(format!("{:?}", report.ai_difficulty));
+                ui.end_row();
+                ui.label("Rallies");
+                ui.label(report.rallies.len().to_string());
+                ui.end_row();
+                ui.label("Longest rally");
+                ui.label(report.longest_rally.to_string());
+                ui.end_row();
+                ui.label("Average rally");
+                ui.label(format!("{:.1}", report.average_rally));
+                ui.end_row();
+                ui.label("Top ball speed");
+                ui.label(format!("{:.0}", report.top_ball_speed));
+                ui.end_row();
+            });
+            ui.separator();
+
+            egui::Grid::new("paddle_stats")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Paddle");
+                    ui.strong("Side");
+                    ui.strong("Controller");
+                    ui.strong("Hits");
+                    ui.strong("Goals");
+                    ui.end_row();
+                    for paddle in report.paddles.iter() {
+                        ui.label(paddle.id.to_string());
+                        ui.label(format!("{:?}", paddle.side));
+                        ui.label(format!("{:?}", paddle.controller));
+                        ui.label(paddle.hits.to_string());
+                        ui.label(paddle.goals.to_string());
+                        ui.end_row();
+                    }
+                    for (name, summary) in [("AI", &report.ai), ("Human", &report.human)] {
+                        ui.strong(name);
+                        ui.label("");
+                        ui.label(format!("{} paddles", summary.paddles));
+                        ui.label(summary.hits.to_string());
+                        ui.label(summary.goals.to_string());
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+
+            if ui.button("Export JSON").clicked() {
+                *export_result = Some(match report.export() {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(err) => format!("Export failed: {err}"),
+                });
+            }
+            if let Some(result) = export_result.as_ref() {
+                ui.label(result);
+            }
+        });
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchStats>()
+            .add_system(track_paddles_system)
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(reset_stats_system))
+            .add_system(track_ball_speed_system)
+            .add_system(record_hits_system)
+            .add_system(record_goals_system)
+            .add_system(record_serves_system)
+            .add_system(draw_match_over_screen);
+    }
+}
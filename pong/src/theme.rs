@@ -0,0 +1,200 @@
+use bevy::{ecs::query::ChangeTrackers, prelude::*};
+use bevy_egui::EguiSettings;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::Ball,
+    breakout::Brick,
+    player::{Player, Side},
+    practice::Target,
+};
+
+pub const MIN_FONT_SCALE: f32 = 0.5;
+pub const MAX_FONT_SCALE: f32 = 2.;
+
+pub struct ThemePlugin;
+
+pub struct Palette {
+    pub background: Color,
+    pub left: Color,
+    pub right: Color,
+    pub ball: Color,
+    pub brick: Color,
+    pub target: Color,
+    pub text: Color,
+    pub accent: Color,
+}
+
+impl Palette {
+    pub fn team(&self, side: Side) -> Color {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteId {
+    Classic,
+    OkabeIto,
+    Tritan,
+    HighContrast,
+}
+
+impl PaletteId {
+    pub const ALL: [PaletteId; 4] = [
+        PaletteId::Classic,
+        PaletteId::OkabeIto,
+        PaletteId::Tritan,
+        PaletteId::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteId::Classic => "Classic",
+            PaletteId::OkabeIto => "Colourblind safe (red-green)",
+            PaletteId::Tritan => "Colourblind safe (blue-yellow)",
+            PaletteId::HighContrast => "High contrast",
+        }
+    }
+
+    // Palety dla daltonistów bazują na zestawie Okabe-Ito
+    pub fn palette(self) -> Palette {
+        match self {
+            PaletteId::Classic => Palette {
+                background: Color::rgb(0.4, 0.4, 0.4),
+                left: Color::rgb(0.8, 0.8, 1.0),
+                right: Color::rgb(0.8, 0.8, 1.0),
+                ball: Color::rgb(0.8, 0.8, 1.0),
+                brick: Color::rgb(0.8, 0.5, 0.4),
+                target: Color::rgb(1.0, 0.8, 0.2),
+                text: Color::WHITE,
+                accent: Color::YELLOW,
+            },
+            PaletteId::OkabeIto => Palette {
+                background: Color::rgb(0.1, 0.1, 0.1),
+                left: Color::rgb(0.0, 0.447, 0.698),
+                right: Color::rgb(0.902, 0.624, 0.0),
+                ball: Color::rgb(0.941, 0.894, 0.259),
+                brick: Color::rgb(0.8, 0.475, 0.655),
+                target: Color::rgb(0.0, 0.62, 0.451),
+                text: Color::WHITE,
+                accent: Color::rgb(0.337, 0.706, 0.914),
+            },
+            PaletteId::Tritan => Palette {
+                background: Color::rgb(0.1, 0.1, 0.1),
+                left: Color::rgb(0.835, 0.369, 0.0),
+                right: Color::rgb(0.0, 0.62, 0.451),
+                ball: Color::WHITE,
+                brick: Color::rgb(0.8, 0.475, 0.655),
+                target: Color::rgb(0.337, 0.706, 0.914),
+                text: Color::WHITE,
+                accent: Color::rgb(0.835, 0.369, 0.0),
+            },
+            PaletteId::HighContrast => Palette {
+                background: Color::BLACK,
+                left: Color::rgb(1.0, 1.0, 0.0),
+                right: Color::rgb(0.0, 1.0, 1.0),
+                ball: Color::WHITE,
+                brick: Color::rgb(1.0, 0.0, 1.0),
+                target: Color::rgb(0.0, 1.0, 0.0),
+                text: Color::WHITE,
+                accent: Color::rgb(1.0, 1.0, 0.0),
+            },
+        }
+    }
+}
+
+// Zapisywane w profilu gracza, patrz profile.rs
+#[derive(Resource, Clone)]
+pub struct VisualSettings {
+    pub palette: PaletteId,
+    pub font_scale: f32,
+}
+
+impl Default for VisualSettings {
+    fn default() -> Self {
+        Self {
+            palette: PaletteId::Classic,
+            font_scale: 1.,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TextRole {
+    Text,
+    Accent,
+    Team(Side),
+}
+
+// Każda sekcja tekstu dostaje kolor według swojej roli; rozmiar jest skalowany z ustawień
+#[derive(Component)]
+pub struct ThemedText {
+    pub font_size: f32,
+    pub roles: &'static [TextRole],
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_theme_system(
+    settings: Res<VisualSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut paddle_query: Query<(&Player, ChangeTrackers<Player>, &mut Sprite)>,
+    mut ball_query: Query<(ChangeTrackers<Ball>, &mut Sprite), Without<Player>>,
+    mut brick_query: Query<(ChangeTrackers<Brick>, &mut Sprite), (Without<Player>, Without<Ball>)>,
+    mut target_query: Query<
+        (ChangeTrackers<Target>, &mut Sprite),
+        (Without<Player>, Without<Ball>, Without<Brick>),
+    >,
+    mut text_query: Query<(&ThemedText, ChangeTrackers<ThemedText>, &mut Text)>,
+) {
+    let palette = settings.palette.palette();
+    let refresh = settings.is_changed();
+
+    if refresh {
+        clear_color.0 = palette.background;
+        egui_settings.scale_factor = settings.font_scale as f64;
+    }
+    for (player, tracker, mut sprite) in paddle_query.iter_mut() {
+        if refresh || tracker.is_added() {
+            sprite.color = palette.team(player.side);
+        }
+    }
+    for (tracker, mut sprite) in ball_query.iter_mut() {
+        if refresh || tracker.is_added() {
+            sprite.color = palette.ball;
+        }
+    }
+    for (tracker, mut sprite) in brick_query.iter_mut() {
+        if refresh || tracker.is_added() {
+            sprite.color = palette.brick;
+        }
+    }
+    for (tracker, mut sprite) in target_query.iter_mut() {
+        if refresh || tracker.is_added() {
+            sprite.color = palette.target;
+        }
+    }
+    for (themed, tracker, mut text) in text_query.iter_mut() {
+        if !refresh && !tracker.is_added() {
+            continue;
+        }
+        for (section, role) in text.sections.iter_mut().zip(themed.roles.iter()) {
+            section.style.font_size = themed.font_size * settings.font_scale;
+            section.style.color = match *role {
+                TextRole::Text => palette.text,
+                TextRole::Accent => palette.accent,
+                TextRole::Team(side) => palette.team(side),
+            };
+        }
+    }
+}
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisualSettings>()
+            .add_system(apply_theme_system);
+    }
+}
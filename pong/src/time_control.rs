@@ -0,0 +1,167 @@
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use bevy_egui::EguiContext;
+
+use crate::{
+    theme::{TextRole, ThemedText},
+    GameState,
+};
+
+const MIN_TIME_SCALE: f32 = 1. / 16.;
+const MAX_TIME_SCALE: f32 = 4.;
+const STEP_SECONDS: f32 = 1. / 60.;
+
+pub struct TimeControlPlugin;
+
+#[derive(Resource)]
+pub struct TimeControl {
+    pub scale: f32,
+    pub frozen: bool,
+    step_requested: bool,
+    stepping: bool,
+    delta: f32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            frozen: false,
+            step_requested: false,
+            stepping: false,
+            delta: 0.,
+        }
+    }
+}
+
+impl TimeControl {
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn request_step(&mut self) {
+        self.step_requested = true;
+    }
+}
+
+#[derive(Component)]
+struct TimeControlText;
+
+// Symulacja działa w InGame, chyba że jest zamrożona; krok działa też w pauzie
+pub fn simulation_run_criteria(
+    time_control: Res<TimeControl>,
+    state: Res<State<GameState>>,
+) -> ShouldRun {
+    let running = match state.current() {
+        GameState::InGame => !time_control.frozen || time_control.stepping,
+        GameState::Paused => time_control.stepping,
+        _ => false,
+    };
+    if running {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn update_time_control_system(mut time_control: ResMut<TimeControl>, time: Res<Time>) {
+    time_control.stepping = time_control.step_requested;
+    time_control.step_requested = false;
+
+    time_control.delta = if time_control.stepping {
+        STEP_SECONDS
+    } else if time_control.frozen {
+        0.
+    } else {
+        time.delta_seconds() * time_control.scale
+    };
+}
+
+// Skróty działają tylko w meczu i nie przejmują klawiszy wpisywanych w pola egui
+fn time_control_input_system(
+    mut time_control: ResMut<TimeControl>,
+    mut egui_context: ResMut<EguiContext>,
+    state: Res<State<GameState>>,
+    input: Res<Input<KeyCode>>,
+) {
+    if !matches!(state.current(), GameState::InGame | GameState::Paused)
+        || egui_context.ctx_mut().wants_keyboard_input()
+    {
+        return;
+    }
+    if input.just_pressed(KeyCode::LBracket) {
+        time_control.scale = (time_control.scale / 2.).max(MIN_TIME_SCALE);
+    }
+    if input.just_pressed(KeyCode::RBracket) {
+        time_control.scale = (time_control.scale * 2.).min(MAX_TIME_SCALE);
+    }
+    if input.just_pressed(KeyCode::Backslash) {
+        time_control.scale = 1.;
+    }
+    if input.just_pressed(KeyCode::Space) {
+        time_control.frozen = !time_control.frozen;
+    }
+    if input.just_pressed(KeyCode::N) {
+        time_control.request_step();
+    }
+}
+
+fn setup_time_control_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TimeControlText,
+        ThemedText {
+            font_size: 24.0,
+            roles: &[TextRole::Accent],
+        },
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraCode-Regular.ttf"),
+                font_size: 24.0,
+                color: Color::YELLOW,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+fn update_time_control_ui(
+    time_control: Res<TimeControl>,
+    state: Res<State<GameState>>,
+    mut text_query: Query<&mut Text, With<TimeControlText>>,
+) {
+    let mut indicator = Vec::new();
+    if time_control.scale != 1. {
+        indicator.push(format!("{}x", time_control.scale));
+    }
+    if time_control.frozen {
+        indicator.push("FROZEN".to_string());
+    }
+    if time_control.frozen || state.current() == &GameState::Paused {
+        indicator.push("[N] step".to_string());
+    }
+    let indicator = indicator.join("  ");
+
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != indicator {
+            text.sections[0].value = indicator.clone();
+        }
+    }
+}
+
+impl Plugin for TimeControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeControl>()
+            .add_startup_system(setup_time_control_ui)
+            .add_system_to_stage(CoreStage::PreUpdate, update_time_control_system)
+            .add_system(time_control_input_system)
+            .add_system(update_time_control_ui);
+    }
+}
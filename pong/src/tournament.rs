@@ -0,0 +1,674 @@
+use std::{cmp::Ordering, fs, io, path::Path};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::{Controller, KeyBindings},
+    GameMode, GameState, MatchRules, MatchSetup, Score,
+};
+
+const TOURNAMENT_SAVE_PATH: &str = "tournament.json";
+
+pub struct TournamentPlugin;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Entrant {
+    pub name: String,
+    pub ai: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TournamentFormat {
+    SingleElimination,
+    RoundRobin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Entrant(usize),
+    Bye,
+    Undecided,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Fixture {
+    pub left: Slot,
+    pub right: Slot,
+    pub score: Option<[i32; 2]>,
+}
+
+impl Fixture {
+    fn new(left: Slot, right: Slot) -> Self {
+        Self {
+            left,
+            right,
+            score: None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match (self.left, self.right) {
+            (Slot::Entrant(left), Slot::Bye) => Some(left),
+            (Slot::Bye, Slot::Entrant(right)) => Some(right),
+            // Remis nikogo nie wyłania
+            (Slot::Entrant(left), Slot::Entrant(right)) => {
+                let [left_score, right_score] = self.score?;
+                match left_score.cmp(&right_score) {
+                    Ordering::Greater => Some(left),
+                    Ordering::Less => Some(right),
+                    Ordering::Equal => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_playable(&self) -> bool {
+        matches!(
+            (self.left, self.right),
+            (Slot::Entrant(_), Slot::Entrant(_))
+        ) && self.score.is_none()
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Standing {
+    pub entrant: usize,
+    pub played: u32,
+    pub wins: u32,
+    pub points_for: i32,
+    pub points_against: i32,
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct Tournament {
+    pub format: TournamentFormat,
+    pub entrants: Vec<Entrant>,
+    pub rounds: Vec<Vec<Fixture>>,
+    #[serde(default)]
+    current: Option<(usize, usize)>,
+}
+
+impl Tournament {
+    pub fn new(format: TournamentFormat, entrants: Vec<Entrant>) -> Self {
+        let rounds = match format {
+            TournamentFormat::SingleElimination => single_elimination_rounds(entrants.len()),
+            TournamentFormat::RoundRobin => round_robin_rounds(entrants.len()),
+        };
+        let mut tournament = Self {
+            format,
+            entrants,
+            rounds,
+            current: None,
+        };
+        tournament.advance();
+        tournament
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut tournament: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        tournament.current = None;
+        Ok(tournament)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn next_fixture(&self) -> Option<(usize, usize)> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .find_map(|(round, fixtures)| {
+                fixtures
+                    .iter()
+                    .position(Fixture::is_playable)
+                    .map(|index| (round, index))
+            })
+    }
+
+    pub fn fixture(&self, (round, index): (usize, usize)) -> &Fixture {
+        &self.rounds[round][index]
+    }
+
+    pub fn current_fixture(&self) -> Option<&Fixture> {
+        self.current.map(|fixture| self.fixture(fixture))
+    }
+
+    // Remisu w drabince nie zapisujemy - mecz zostaje do rozegrania od nowa
+    pub fn record_result(&mut self, (round, index): (usize, usize), score: [i32; 2]) -> bool {
+        if self.format == TournamentFormat::SingleElimination && score[0] == score[1] {
+            return false;
+        }
+        self.rounds[round][index].score = Some(score);
+        self.advance();
+        true
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        match self.format {
+            TournamentFormat::SingleElimination => self.rounds.last()?.first()?.winner(),
+            TournamentFormat::RoundRobin => match self.next_fixture() {
+                Some(_) => None,
+                None => self.standings().first().map(|standing| standing.entrant),
+            },
+        }
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.entrants.len())
+            .map(|entrant| Standing {
+                entrant,
+                ..default()
+            })
+            .collect();
+
+        for fixture in self.rounds.iter().flatten() {
+            if let (Slot::Entrant(left), Slot::Entrant(right), Some([left_score, right_score])) =
+                (fixture.left, fixture.right, fixture.score)
+            {
+                for (entrant, scored, conceded) in [
+                    (left, left_score, right_score),
+                    (right, right_score, left_score),
+                ] {
+                    let standing = &mut standings[entrant];
+                    standing.played += 1;
+                    standing.points_for += scored;
+                    standing.points_against += conceded;
+                    if scored > conceded {
+                        standing.wins += 1;
+                    }
+                }
+            }
+        }
+
+        standings.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then((b.points_for - b.points_against).cmp(&(a.points_for - a.points_against)))
+        });
+        standings
+    }
+
+    pub fn slot_name(&self, slot: Slot) -> String {
+        match slot {
+            Slot::Entrant(index) => {
+                let entrant = &self.entrants[index];
+                if entrant.ai {
+                    format!("{} (AI)", entrant.name)
+                } else {
+                    entrant.name.clone()
+                }
+            }
+            Slot::Bye => "bye".to_string(),
+            Slot::Undecided => "?".to_string(),
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.format != TournamentFormat::SingleElimination {
+            return;
+        }
+        for round in 1..self.rounds.len() {
+            for index in 0..self.rounds[round].len() {
+                let slot_from = |fixture: &Fixture| match fixture.winner() {
+                    Some(winner) => Slot::Entrant(winner),
+                    None => Slot::Undecided,
+                };
+                let left = slot_from(&self.rounds[round - 1][index * 2]);
+                let right = slot_from(&self.rounds[round - 1][index * 2 + 1]);
+                let fixture = &mut self.rounds[round][index];
+                fixture.left = left;
+                fixture.right = right;
+            }
+        }
+    }
+}
+
+// Kolejność rozstawionych w drabince: 1, 8, 4, 5, 2, 7, 3, 6 dla ośmiu miejsc. Każdy
+// rozstawiony dostaje w parze dopełnienie do rozmiaru, więc dwaj najlepsi trafiają
+// na siebie dopiero w finale
+fn bracket_order(size: usize) -> Vec<usize> {
+    if size <= 2 {
+        return (0..size).collect();
+    }
+    bracket_order(size / 2)
+        .into_iter()
+        .flat_map(|seed| [seed, size - 1 - seed])
+        .collect()
+}
+
+// Wolne losy trafiają do najwyżej rozstawionych
+fn single_elimination_rounds(entrants: usize) -> Vec<Vec<Fixture>> {
+    let size = entrants.next_power_of_two().max(2);
+    let seed = |index: usize| {
+        if index < entrants {
+            Slot::Entrant(index)
+        } else {
+            Slot::Bye
+        }
+    };
+
+    let order = bracket_order(size);
+    let mut rounds = vec![order
+        .chunks(2)
+        .map(|pair| Fixture::new(seed(pair[0]), seed(pair[1])))
+        .collect::<Vec<_>>()];
+
+    let mut fixtures = size / 4;
+    while fixtures > 0 {
+        rounds.push(vec![
+            Fixture::new(Slot::Undecided, Slot::Undecided);
+            fixtures
+        ]);
+        fixtures /= 2;
+    }
+    rounds
+}
+
+// Metoda kołowa - pierwszy zawodnik stoi w miejscu, reszta się obraca
+fn round_robin_rounds(entrants: usize) -> Vec<Vec<Fixture>> {
+    let mut slots: Vec<Slot> = (0..entrants).map(Slot::Entrant).collect();
+    if slots.len() % 2 == 1 {
+        slots.push(Slot::Bye);
+    }
+
+    let mut rounds = Vec::new();
+    for _ in 1..slots.len() {
+        rounds.push(
+            (0..slots.len() / 2)
+                .map(|index| Fixture::new(slots[index], slots[slots.len() - 1 - index]))
+                .filter(|fixture| fixture.left != Slot::Bye && fixture.right != Slot::Bye)
+                .collect(),
+        );
+        slots[1..].rotate_right(1);
+    }
+    rounds
+}
+
+#[derive(Default)]
+struct TournamentSetup {
+    entrants: Vec<Entrant>,
+    new_name: String,
+    format: Option<TournamentFormat>,
+    error: Option<String>,
+}
+
+fn start_next_match(
+    commands: &mut Commands,
+    tournament: &mut Tournament,
+    rules: &mut MatchRules,
+    bindings: &KeyBindings,
+    app_state: &mut State<GameState>,
+) {
+    let Some(fixture) = tournament.next_fixture() else {
+        return;
+    };
+    tournament.current = Some(fixture);
+
+    let fixture = tournament.fixture(fixture);
+    let mode = GameMode::OneVsOne;
+    let controllers = [fixture.left, fixture.right]
+        .into_iter()
+        .zip(mode.slots())
+        .map(|(entrant, &slot)| match entrant {
+            Slot::Entrant(index) if !tournament.entrants[index].ai => bindings.controller(slot),
+            _ => Controller::Ai,
+        })
+        .collect();
+
+    commands.insert_resource(MatchSetup { mode, controllers });
+    rules.speed = mode.speed_curve();
+    app_state.replace(GameState::InGame).unwrap();
+}
+
+fn close_tournament(mut commands: Commands) {
+    commands.remove_resource::<Tournament>();
+}
+
+fn record_match_result(mut tournament: Option<ResMut<Tournament>>, score_query: Query<&Score>) {
+    let Some(tournament) = tournament.as_mut() else {
+        return;
+    };
+    let Some(fixture) = tournament.current.take() else {
+        return;
+    };
+    let score = score_query.get_single().unwrap();
+    if !tournament.record_result(fixture, [score.player1_score, score.player2_score]) {
+        warn!("Elimination match ended in a draw, it will be replayed");
+        return;
+    }
+    if let Err(err) = tournament.save(TOURNAMENT_SAVE_PATH) {
+        error!("Failed to save tournament: {err}");
+    }
+}
+
+fn draw_tournament_setup(ui: &mut egui::Ui, commands: &mut Commands, setup: &mut TournamentSetup) {
+    let format = setup
+        .format
+        .get_or_insert(TournamentFormat::SingleElimination);
+    ui.horizontal(|ui| {
+        ui.radio_value(
+            format,
+            TournamentFormat::SingleElimination,
+            "Single elimination",
+        );
+        ui.radio_value(format, TournamentFormat::RoundRobin, "Round robin");
+    });
+    ui.separator();
+
+    let mut removed = None;
+    for (index, entrant) in setup.entrants.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut entrant.name);
+            ui.checkbox(&mut entrant.ai, "AI");
+            if ui.button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        setup.entrants.remove(index);
+    }
+
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut setup.new_name);
+        for (label, ai) in [("Add player", false), ("Add AI", true)] {
+            if ui.button(label).clicked() {
+                let name = match setup.new_name.trim() {
+                    "" if ai => format!("AI {}", setup.entrants.len() + 1),
+                    "" => format!("Player {}", setup.entrants.len() + 1),
+                    name => name.to_string(),
+                };
+                setup.entrants.push(Entrant { name, ai });
+                setup.new_name.clear();
+            }
+        }
+    });
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(setup.entrants.len() >= 2, egui::Button::new("Start"))
+            .clicked()
+        {
+            let tournament = Tournament::new(
+                setup.format.unwrap_or(TournamentFormat::SingleElimination),
+                setup.entrants.clone(),
+            );
+            if let Err(err) = tournament.save(TOURNAMENT_SAVE_PATH) {
+                error!("Failed to save tournament: {err}");
+            }
+            commands.insert_resource(tournament);
+        }
+        if ui
+            .add_enabled(
+                Path::new(TOURNAMENT_SAVE_PATH).exists(),
+                egui::Button::new("Continue saved"),
+            )
+            .clicked()
+        {
+            match Tournament::load(TOURNAMENT_SAVE_PATH) {
+                Ok(tournament) => commands.insert_resource(tournament),
+                Err(err) => setup.error = Some(format!("Failed to load tournament: {err}")),
+            }
+        }
+    });
+    if let Some(error) = setup.error.as_ref() {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+}
+
+fn draw_bracket(ui: &mut egui::Ui, tournament: &Tournament) {
+    let next = tournament.next_fixture();
+    ui.horizontal_top(|ui| {
+        for (round, fixtures) in tournament.rounds.iter().enumerate() {
+            ui.vertical(|ui| {
+                ui.strong(format!("Round {}", round + 1));
+                for (index, fixture) in fixtures.iter().enumerate() {
+                    ui.group(|ui| {
+                        let (left_score, right_score) = match fixture.score {
+                            Some([left, right]) => (left.to_string(), right.to_string()),
+                            None => (String::new(), String::new()),
+                        };
+                        for (slot, score) in
+                            [(fixture.left, left_score), (fixture.right, right_score)]
+                        {
+                            let text = egui::RichText::new(format!(
+                                "{} {}",
+                                tournament.slot_name(slot),
+                                score
+                            ));
+                            if Some((round, index)) == next {
+                                ui.label(text.strong());
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+                }
+            });
+        }
+    });
+
+    if tournament.format == TournamentFormat::RoundRobin {
+        ui.separator();
+        egui::Grid::new("standings").striped(true).show(ui, |ui| {
+            for header in ["Entrant", "Played", "Wins", "Points"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for standing in tournament.standings() {
+                ui.label(tournament.slot_name(Slot::Entrant(standing.entrant)));
+                ui.label(standing.played.to_string());
+                ui.label(standing.wins.to_string());
+                ui.label(format!(
+                    "{}:{}",
+                    standing.points_for, standing.points_against
+                ));
+                ui.end_row();
+            }
+        });
+    }
+
+    if let Some(champion) = tournament.champion() {
+        ui.separator();
+        ui.heading(format!(
+            "Champion: {}",
+            tournament.slot_name(Slot::Entrant(champion))
+        ));
+    }
+}
+
+fn draw_tournament_menu(
+    mut commands: Commands,
+    mut egui_context: ResMut<EguiContext>,
+    mut app_state: ResMut<State<GameState>>,
+    mut tournament: Option<ResMut<Tournament>>,
+    mut rules: ResMut<MatchRules>,
+    bindings: Res<KeyBindings>,
+    mut setup: Local<TournamentSetup>,
+) {
+    let in_bracket = app_state.current() == &GameState::Tournament;
+    let after_match = app_state.current() == &GameState::MatchOver && tournament.is_some();
+    if !in_bracket && !after_match {
+        return;
+    }
+
+    egui::Window::new("Tournament")
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0., 20.))
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            if after_match {
+                if ui.button("Back to bracket").clicked() {
+                    app_state.replace(GameState::Tournament).unwrap();
+                }
+                return;
+            }
+
+            match tournament.as_mut() {
+                None => draw_tournament_setup(ui, &mut commands, &mut setup),
+                Some(tournament) => {
+                    draw_bracket(ui, tournament);
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                tournament.next_fixture().is_some(),
+                                egui::Button::new("Play next match"),
+                            )
+                            .clicked()
+                        {
+                            start_next_match(
+                                &mut commands,
+                                tournament,
+                                &mut rules,
+                                &bindings,
+                                &mut app_state,
+                            );
+                        }
+                        if ui.button("New tournament").clicked() {
+                            commands.remove_resource::<Tournament>();
+                        }
+                    });
+                }
+            }
+
+            if ui.button("Main menu").clicked() {
+                app_state.replace(GameState::MainMenu).unwrap();
+            }
+        });
+}
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(draw_tournament_menu)
+            .add_system_set(
+                SystemSet::on_enter(GameState::MatchOver).with_system(record_match_result),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(close_tournament));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn entrants(count: usize) -> Vec<Entrant> {
+        (0..count)
+            .map(|index| Entrant {
+                name: format!("P{}", index + 1),
+                ai: false,
+            })
+            .collect()
+    }
+
+    fn pairs(fixtures: &[Fixture]) -> Vec<(Slot, Slot)> {
+        fixtures
+            .iter()
+            .map(|fixture| (fixture.left, fixture.right))
+            .collect()
+    }
+
+    // Wyżej rozstawiony (niższy indeks) zawsze wygrywa
+    fn play_favourites(tournament: &mut Tournament) {
+        while let Some(fixture) = tournament.next_fixture() {
+            let (Slot::Entrant(left), Slot::Entrant(right)) = (
+                tournament.fixture(fixture).left,
+                tournament.fixture(fixture).right,
+            ) else {
+                unreachable!();
+            };
+            let score = if left < right { [10, 5] } else { [5, 10] };
+            assert!(tournament.record_result(fixture, score));
+        }
+    }
+
+    #[test]
+    fn seeds_bracket_in_standard_order() {
+        assert_eq!(bracket_order(8), [0, 7, 3, 4, 1, 6, 2, 5]);
+        let rounds = single_elimination_rounds(8);
+        assert_eq!(
+            pairs(&rounds[0]),
+            [
+                (Slot::Entrant(0), Slot::Entrant(7)),
+                (Slot::Entrant(3), Slot::Entrant(4)),
+                (Slot::Entrant(1), Slot::Entrant(6)),
+                (Slot::Entrant(2), Slot::Entrant(5)),
+            ]
+        );
+        assert_eq!(rounds.iter().map(Vec::len).collect::<Vec<_>>(), [4, 2, 1]);
+    }
+
+    #[test]
+    fn top_seeds_meet_in_final() {
+        for count in [2, 3, 5, 8] {
+            let mut tournament =
+                Tournament::new(TournamentFormat::SingleElimination, entrants(count));
+            play_favourites(&mut tournament);
+            let final_fixture = &tournament.rounds.last().unwrap()[0];
+            assert_eq!(
+                (final_fixture.left, final_fixture.right),
+                (Slot::Entrant(0), Slot::Entrant(1)),
+                "{count} entrants"
+            );
+            assert_eq!(tournament.champion(), Some(0));
+        }
+    }
+
+    #[test]
+    fn byes_go_to_top_seeds() {
+        let tournament = Tournament::new(TournamentFormat::SingleElimination, entrants(5));
+        let byes: Vec<usize> = tournament.rounds[0]
+            .iter()
+            .filter(|fixture| fixture.right == Slot::Bye)
+            .filter_map(Fixture::winner)
+            .collect();
+        assert_eq!(byes, [0, 1, 2]);
+        // Dwaj najlepsi nie grają ze sobą w drugiej rundzie
+        for fixture in &tournament.rounds[1] {
+            assert_ne!(
+                (fixture.left, fixture.right),
+                (Slot::Entrant(0), Slot::Entrant(1))
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_elimination_draws() {
+        let mut tournament = Tournament::new(TournamentFormat::SingleElimination, entrants(2));
+        let fixture = tournament.next_fixture().unwrap();
+        assert!(!tournament.record_result(fixture, [3, 3]));
+        assert_eq!(tournament.next_fixture(), Some(fixture));
+        assert_eq!(tournament.champion(), None);
+
+        let mut tournament = Tournament::new(TournamentFormat::RoundRobin, entrants(2));
+        let fixture = tournament.next_fixture().unwrap();
+        assert!(tournament.record_result(fixture, [3, 3]));
+        assert_eq!(tournament.fixture(fixture).winner(), None);
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        for count in [2, 3, 4, 5, 6] {
+            let rounds = round_robin_rounds(count);
+            let mut met = HashSet::new();
+            for round in &rounds {
+                let mut playing = HashSet::new();
+                for fixture in round {
+                    let (Slot::Entrant(left), Slot::Entrant(right)) = (fixture.left, fixture.right)
+                    else {
+                        panic!("bye left in schedule");
+                    };
+                    assert!(playing.insert(left) && playing.insert(right));
+                    assert!(met.insert((left.min(right), left.max(right))));
+                }
+            }
+            assert_eq!(met.len(), count * (count - 1) / 2, "{count} entrants");
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PROFILES_PATH: &str = "profiles.json";
+const BACKUP_PATH: &str = "profiles.json.bak";
+const DEFAULT_PROFILE_NAME: &str = "Player";
+const MAX_HIGH_SCORES: usize = 10;
+
+// Migracja MIGRATIONS[n] zamienia plik w wersji n + 1 na wersję n + 2.
+// Zmiana formatu = nowa funkcja na końcu listy, starych nie ruszamy.
+type Migration = fn(&mut Value) -> io::Result<()>;
+const MIGRATIONS: &[Migration] = &[];
+pub const CURRENT_VERSION: u64 = 1 + MIGRATIONS.len() as u64;
+
+// Jeden plik profili jest wspólny dla pong, puzzle i rts; każda gra dodaje ten plugin ze swoją nazwą
+pub struct ProfilesPlugin {
+    pub game: &'static str,
+}
+
+#[derive(Resource, Clone, Copy)]
+pub struct ActiveGame(pub &'static str);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreOrder {
+    HigherIsBetter,
+    LowerIsBetter,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: i64,
+    // Sekundy od epoki Uniksa
+    pub achieved_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub sessions: u32,
+    pub play_seconds: f64,
+    #[serde(default)]
+    pub counters: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    // Akcja ("pong.paddle1.up") -> nazwa klawisza
+    #[serde(default)]
+    pub key_bindings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default = "default_font_scale")]
+    pub font_scale: f32,
+    // Gra -> statystyki
+    #[serde(default)]
+    pub stats: BTreeMap<String, LifetimeStats>,
+    // Tablica ("pong.wall_practice") -> wyniki od najlepszego
+    #[serde(default)]
+    pub high_scores: BTreeMap<String, Vec<HighScore>>,
+}
+
+fn default_font_scale() -> f32 {
+    1.
+}
+
+impl Profile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            key_bindings: BTreeMap::new(),
+            theme: None,
+            font_scale: default_font_scale(),
+            stats: BTreeMap::new(),
+            high_scores: BTreeMap::new(),
+        }
+    }
+
+    pub fn stats_mut(&mut self, game: &str) -> &mut LifetimeStats {
+        self.stats.entry(game.to_string()).or_default()
+    }
+
+    pub fn count(&mut self, game: &str, counter: &str, amount: u64) {
+        *self
+            .stats_mut(game)
+            .counters
+            .entry(counter.to_string())
+            .or_default() += amount;
+    }
+
+    pub fn high_scores(&self, board: &str) -> &[HighScore] {
+        self.high_scores.get(board).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn best(&self, board: &str) -> Option<i64> {
+        self.high_scores(board).first().map(|entry| entry.score)
+    }
+
+    // Zwraca miejsce w tabeli, jeśli wynik się do niej załapał
+    pub fn record_score(&mut self, board: &str, score: i64, order: ScoreOrder) -> Option<usize> {
+        let scores = self.high_scores.entry(board.to_string()).or_default();
+        let rank = scores
+            .iter()
+            .position(|entry| match order {
+                ScoreOrder::HigherIsBetter => score > entry.score,
+                ScoreOrder::LowerIsBetter => score < entry.score,
+            })
+            .unwrap_or(scores.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        let achieved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        scores.insert(rank, HighScore { score, achieved_at });
+        scores.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct Profiles {
+    pub active: usize,
+    pub profiles: Vec<Profile>,
+    // Plik z nowszej wersji gry tylko czytamy, żeby nowsza wersja nie straciła postępów
+    #[serde(skip)]
+    read_only: bool,
+}
+
+// Co się stało z plikiem profili przy starcie; gracz widzi to w grze
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProfilesStatus {
+    #[default]
+    Loaded,
+    // Plik z nowszej wersji gry - postępy widać, ale nic nie zapisujemy
+    ReadOnly {
+        version: u64,
+    },
+    // Uszkodzony plik odłożony do kopii, gra zaczyna od pustych profili
+    Reset {
+        backup: Option<String>,
+    },
+}
+
+impl ProfilesStatus {
+    pub fn notice(&self) -> Option<String> {
+        match self {
+            ProfilesStatus::Loaded => None,
+            ProfilesStatus::ReadOnly { version } => Some(format!(
+                "{PROFILES_PATH} comes from a newer version of the game (format {version}). \
+                 Progress from this session won't be saved."
+            )),
+            ProfilesStatus::Reset {
+                backup: Some(backup),
+            } => Some(format!(
+                "{PROFILES_PATH} was damaged and has been moved to {backup}. \
+                 Starting with a new profile."
+            )),
+            ProfilesStatus::Reset { backup: None } => Some(format!(
+                "{PROFILES_PATH} was damaged and could not be backed up. \
+                 Progress from this session won't be saved."
+            )),
+        }
+    }
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: 0,
+            profiles: vec![Profile::new(DEFAULT_PROFILE_NAME)],
+            read_only: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VersionedProfiles<'a> {
+    version: u64,
+    #[serde(flatten)]
+    profiles: &'a Profiles,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn migrate(value: &mut Value, migrations: &[Migration]) -> io::Result<()> {
+    let current = 1 + migrations.len() as u64;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid_data("profiles file has no version".to_string()))?;
+    if version == 0 || version > current {
+        return Err(invalid_data(format!(
+            "profiles file version {version} is not supported (current is {current})"
+        )));
+    }
+    for migration in &migrations[version as usize - 1..] {
+        migration(value)?;
+    }
+    value["version"] = current.into();
+    Ok(())
+}
+
+// Wersja pliku, jeśli jest nowsza niż ta, którą gra umie czytać
+fn newer_version(json: &str) -> Option<u64> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let version = value.get("version")?.as_u64()?;
+    (version > CURRENT_VERSION).then_some(version)
+}
+
+impl Profiles {
+    // Wczytanie, które nigdy nie psuje pliku: nowszy format zostaje tylko do odczytu,
+    // uszkodzony trafia do kopii i dopiero wtedy zaczynamy od nowa
+    pub fn load_or_recover(
+        path: impl AsRef<Path>,
+        backup: impl AsRef<Path>,
+    ) -> (Self, ProfilesStatus) {
+        let path = path.as_ref();
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return (Self::default(), ProfilesStatus::Loaded)
+            }
+            Err(err) => {
+                error!("Failed to read {}: {err}", path.display());
+                return (
+                    Self {
+                        read_only: true,
+                        ..default()
+                    },
+                    ProfilesStatus::Reset { backup: None },
+                );
+            }
+        };
+        let err = match Self::from_json(&json) {
+            Ok(profiles) => return (profiles, ProfilesStatus::Loaded),
+            Err(err) => err,
+        };
+
+        if let Some(version) = newer_version(&json) {
+            warn!(
+                "{} has version {version}, loading it read-only",
+                path.display()
+            );
+            // Nieznane pola pomijamy; jeśli nawet to się nie uda, pokazujemy pusty profil
+            let mut profiles = serde_json::from_str::<Self>(&json)
+                .ok()
+                .filter(|profiles| !profiles.profiles.is_empty())
+                .unwrap_or_default();
+            profiles.active = profiles.active.min(profiles.profiles.len() - 1);
+            profiles.read_only = true;
+            return (profiles, ProfilesStatus::ReadOnly { version });
+        }
+
+        error!("Failed to load {}: {err}", path.display());
+        let backup = backup.as_ref();
+        match fs::rename(path, backup) {
+            Ok(()) => (
+                Self::default(),
+                ProfilesStatus::Reset {
+                    backup: Some(backup.display().to_string()),
+                },
+            ),
+            Err(err) => {
+                error!("Failed to back up {}: {err}", path.display());
+                (
+                    Self {
+                        read_only: true,
+                        ..default()
+                    },
+                    ProfilesStatus::Reset { backup: None },
+                )
+            }
+        }
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let mut value: Value = serde_json::from_str(json)?;
+        migrate(&mut value, MIGRATIONS)?;
+        let mut profiles: Self = serde_json::from_value(value)?;
+        if profiles.profiles.is_empty() {
+            profiles.profiles.push(Profile::new(DEFAULT_PROFILE_NAME));
+        }
+        profiles.active = profiles.active.min(profiles.profiles.len() - 1);
+        Ok(profiles)
+    }
+
+    pub fn to_json(&self) -> io::Result<String> {
+        Ok(serde_json::to_string_pretty(&VersionedProfiles {
+            version: CURRENT_VERSION,
+            profiles: self,
+        })?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "profiles were loaded read-only",
+            ));
+        }
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn save_or_log(&self) {
+        if let Err(err) = self.save(PROFILES_PATH) {
+            error!("Failed to save profiles: {err}");
+        }
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.profiles[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        &mut self.profiles[self.active]
+    }
+
+    pub fn add(&mut self, name: impl Into<String>) -> usize {
+        self.profiles.push(Profile::new(name));
+        self.profiles.len() - 1
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.profiles.len() {
+            self.active = index;
+        }
+    }
+}
+
+fn load_profiles_system(mut commands: Commands) {
+    let (profiles, status) = Profiles::load_or_recover(PROFILES_PATH, BACKUP_PATH);
+    commands.insert_resource(profiles);
+    commands.insert_resource(status);
+}
+
+// Gry bez egui (rts) mają tylko wpis w logu z load_or_recover
+fn draw_notice_system(
+    egui_context: Option<ResMut<EguiContext>>,
+    status: Res<ProfilesStatus>,
+    mut dismissed: Local<bool>,
+) {
+    let (Some(mut egui_context), Some(notice)) = (egui_context, status.notice()) else {
+        return;
+    };
+    if *dismissed {
+        return;
+    }
+    egui::Window::new("Profiles")
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0., -10.))
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(notice);
+            *dismissed = ui.button("OK").clicked();
+        });
+}
+
+fn start_session_system(mut profiles: ResMut<Profiles>, game: Res<ActiveGame>) {
+    profiles.active_mut().stats_mut(game.0).sessions += 1;
+    profiles.save_or_log();
+}
+
+fn save_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    mut profiles: ResMut<Profiles>,
+    game: Res<ActiveGame>,
+    time: Res<Time>,
+) {
+    if exit_events.iter().next().is_none() {
+        return;
+    }
+    profiles.active_mut().stats_mut(game.0).play_seconds += time.elapsed_seconds_f64();
+    profiles.save_or_log();
+}
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profiles>()
+            .init_resource::<ProfilesStatus>()
+            .insert_resource(ActiveGame(self.game))
+            .add_startup_system_to_stage(StartupStage::PreStartup, load_profiles_system)
+            .add_startup_system(start_session_system)
+            .add_system(draw_notice_system)
+            .add_system_to_stage(CoreStage::Last, save_on_exit_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_to_nick(value: &mut Value) -> io::Result<()> {
+        for profile in value["profiles"].as_array_mut().into_iter().flatten() {
+            if let Some(name) = profile.as_object_mut().and_then(|p| p.remove("name")) {
+                profile["nick"] = name;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_keeps_progress() {
+        let mut profiles = Profiles::default();
+        profiles.active_mut().count("pong", "goals", 3);
+        profiles
+            .active_mut()
+            .record_score("pong.wall_practice", 12, ScoreOrder::HigherIsBetter);
+
+        let loaded = Profiles::from_json(&profiles.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.active().stats["pong"].counters["goals"], 3);
+        assert_eq!(loaded.active().best("pong.wall_practice"), Some(12));
+    }
+
+    #[test]
+    fn migrations_run_from_file_version() {
+        let mut value = serde_json::json!({
+            "version": 1,
+            "active": 0,
+            "profiles": [{ "name": "Ala" }],
+        });
+        migrate(&mut value, &[rename_to_nick]).unwrap();
+        assert_eq!(value["version"], 2);
+        assert_eq!(value["profiles"][0]["nick"], "Ala");
+
+        // Plik już w najnowszej wersji nie jest migrowany ponownie
+        migrate(&mut value, &[rename_to_nick]).unwrap();
+        assert_eq!(value["profiles"][0]["nick"], "Ala");
+    }
+
+    #[test]
+    fn newer_or_unversioned_files_are_rejected() {
+        let newer = format!(
+            r#"{{"version": {}, "active": 0, "profiles": []}}"#,
+            CURRENT_VERSION + 1
+        );
+        assert!(Profiles::from_json(&newer).is_err());
+        assert!(Profiles::from_json(r#"{"active": 0, "profiles": []}"#).is_err());
+    }
+
+    #[test]
+    fn newer_files_stay_read_only_and_corrupt_files_are_backed_up() {
+        let dir = std::env::temp_dir().join(format!("profiles-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (path, backup) = (dir.join("profiles.json"), dir.join("profiles.json.bak"));
+
+        let newer = format!(
+            r#"{{"version": {}, "active": 0, "profiles": [{{"name": "Ala", "pets": 3}}]}}"#,
+            CURRENT_VERSION + 1
+        );
+        fs::write(&path, &newer).unwrap();
+        let (profiles, status) = Profiles::load_or_recover(&path, &backup);
+        assert_eq!(
+            status,
+            ProfilesStatus::ReadOnly {
+                version: CURRENT_VERSION + 1
+            }
+        );
+        assert_eq!(profiles.active().name, "Ala");
+        assert!(profiles.save(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+        fs::write(&path, r#"{"version": 1, "active": 0, "prof"#).unwrap();
+        let (profiles, status) = Profiles::load_or_recover(&path, &backup);
+        assert!(matches!(status, ProfilesStatus::Reset { backup: Some(_) }));
+        assert!(!path.exists() && backup.exists());
+        profiles.save(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn high_scores_respect_order_and_limit() {
+        let mut profile = Profile::new("Ala");
+        for time in [30, 10, 20] {
+            profile.record_score("puzzle.level1", time, ScoreOrder::LowerIsBetter);
+        }
+        let times: Vec<i64> = profile
+            .high_scores("puzzle.level1")
+            .iter()
+            .map(|entry| entry.score)
+            .collect();
+        assert_eq!(times, [10, 20, 30]);
+
+        for score in 0..MAX_HIGH_SCORES as i64 {
+            profile.record_score("pong.target_drill", score, ScoreOrder::HigherIsBetter);
+        }
+        assert_eq!(
+            profile.record_score("pong.target_drill", -1, ScoreOrder::HigherIsBetter),
+            None
+        );
+        assert_eq!(
+            profile.record_score("pong.target_drill", 100, ScoreOrder::HigherIsBetter),
+            Some(0)
+        );
+        assert_eq!(
+            profile.high_scores("pong.target_drill").len(),
+            MAX_HIGH_SCORES
+        );
+    }
+}
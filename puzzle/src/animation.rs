@@ -4,14 +4,18 @@ use bevy::prelude::*;
 
 #[derive(Default)]
 pub struct AnimationData {
-    start_index: usize,
-    len: usize,
+    frames: Vec<usize>,
     timer: Timer,
 }
 
 impl AnimationData {
     pub fn from_frames(start_index : usize, len : usize) -> Self {
-        Self { start_index, len, timer: Timer::from_seconds(1.0, TimerMode::Once) }
+        Self::from_frame_list((start_index..start_index + len).collect())
+    }
+
+    // Klatki w dowolnej kolejności, np. z klipu w opisie atlasu
+    pub fn from_frame_list(frames: Vec<usize>) -> Self {
+        Self { frames, timer: Timer::from_seconds(1.0, TimerMode::Once) }
     }
 
     pub fn with_duration(mut self, duration: Duration) -> Self {
@@ -57,11 +61,11 @@ impl FrameAnimation for SimpleAnimation {
         
         anim.timer.tick(time);
         let frames_to_advance = anim.timer.times_finished_this_tick();
-        self.current = (self.current + frames_to_advance as usize) % anim.len;
+        self.current = (self.current + frames_to_advance as usize) % anim.frames.len();
     }
 
     fn current_frame(&self) -> usize {
-        self.current + self.data.start_index
+        self.data.frames[self.current]
     }
 }
 
@@ -87,6 +91,10 @@ impl<State: Hash + Eq + Sync + Send + 'static> AnimationStateMachine<State> {
         if new_state != self.current_state  {
             self.current_state = new_state;
             self.current_frame = 0;
+            // Nowy stan zaczyna od pełnej pierwszej klatki, np. chód razem z krokiem
+            if let Some(anim) = self.map.get_mut(&self.current_state) {
+                anim.timer.reset();
+            }
         }
     }
 }
@@ -97,11 +105,11 @@ impl<State: Hash + Eq + Sync + Send + 'static> FrameAnimation for AnimationState
         
         anim.timer.tick(time);
         let frames_to_advance = anim.timer.times_finished_this_tick();
-        self.current_frame = (self.current_frame + frames_to_advance as usize) % anim.len;
+        self.current_frame = (self.current_frame + frames_to_advance as usize) % anim.frames.len();
     }
 
     fn current_frame(&self) -> usize {
-        self.current_frame + self.map.get(&self.current_state).unwrap().start_index
+        self.map.get(&self.current_state).unwrap().frames[self.current_frame]
     }
 }
 
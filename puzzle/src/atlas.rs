@@ -0,0 +1,309 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_egui::{egui, EguiContext};
+use serde::Deserialize;
+
+use crate::{animation::AnimationData, GameState};
+
+const ATLAS_META: &str = "atlases/sokoban.atlas.json";
+
+// Klipy, których gra szuka po nazwie; brak któregoś wychodzi przy wczytywaniu opisu
+pub const REQUIRED_CLIPS: [&str; 8] = [
+    "player_idle_down",
+    "player_idle_up",
+    "player_idle_left",
+    "player_idle_right",
+    "player_walk_down",
+    "player_walk_up",
+    "player_walk_left",
+    "player_walk_right",
+];
+
+pub struct AtlasPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Region {
+    Wall,
+    Floor,
+    Goal,
+    Box,
+    BoxOnGoal,
+    Player,
+}
+
+impl Region {
+    pub const ALL: [Region; 6] = [
+        Region::Wall,
+        Region::Floor,
+        Region::Goal,
+        Region::Box,
+        Region::BoxOnGoal,
+        Region::Player,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Region::Wall => "wall",
+            Region::Floor => "floor",
+            Region::Goal => "goal",
+            Region::Box => "box",
+            Region::BoxOnGoal => "box_on_goal",
+            Region::Player => "player",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Clip {
+    pub frames: Vec<usize>,
+    // Bez czasu klatki tempo ustala kod - chód gracza idzie w rytm kroku
+    #[serde(default)]
+    pub frame_seconds: Option<f32>,
+}
+
+// Opis siatki sprite'ów z pliku *.atlas.json; tekstura leży obok pliku opisu
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "71939454-774e-4329-82f2-10a3a17320f0"]
+pub struct AtlasMeta {
+    pub texture: String,
+    pub tile_size: [f32; 2],
+    pub columns: usize,
+    pub rows: usize,
+    pub regions: HashMap<String, usize>,
+    #[serde(default)]
+    pub clips: HashMap<String, Clip>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasError {
+    pub message: String,
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for AtlasError {}
+
+impl AtlasMeta {
+    // Klatki spoza siatki wyszłyby na jaw dopiero przy rysowaniu
+    pub fn validate(&self) -> Result<(), AtlasError> {
+        let len = self.columns * self.rows;
+        let frames = self
+            .regions
+            .iter()
+            .map(|(name, &index)| (name, index))
+            .chain(
+                self.clips
+                    .iter()
+                    .flat_map(|(name, clip)| clip.frames.iter().map(move |&index| (name, index))),
+            );
+        for (name, index) in frames {
+            if index >= len {
+                return Err(AtlasError {
+                    message: format!("'{name}' uses frame {index}, atlas has {len}"),
+                });
+            }
+        }
+        if let Some((name, _)) = self.clips.iter().find(|(_, clip)| clip.frames.is_empty()) {
+            return Err(AtlasError {
+                message: format!("clip '{name}' has no frames"),
+            });
+        }
+        if let Some(region) = Region::ALL
+            .into_iter()
+            .find(|region| !self.regions.contains_key(region.name()))
+        {
+            return Err(AtlasError {
+                message: format!("region '{}' is missing", region.name()),
+            });
+        }
+        if let Some(name) = REQUIRED_CLIPS
+            .into_iter()
+            .find(|&name| !self.clips.contains_key(name))
+        {
+            return Err(AtlasError {
+                message: format!("clip '{name}' is missing"),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AtlasMetaLoader;
+
+impl AssetLoader for AtlasMetaLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let meta: AtlasMeta = serde_json::from_slice(bytes)?;
+            meta.validate()?;
+            let texture_path =
+                AssetPath::new(load_context.path().with_file_name(&meta.texture), None);
+            let texture = load_context.get_handle(texture_path.clone());
+            let atlas = TextureAtlas::from_grid(
+                texture,
+                Vec2::from(meta.tile_size),
+                meta.columns,
+                meta.rows,
+                None,
+                None,
+            );
+            load_context.set_labeled_asset(
+                "atlas",
+                LoadedAsset::new(atlas).with_dependency(texture_path.clone()),
+            );
+            load_context.set_default_asset(LoadedAsset::new(meta).with_dependency(texture_path));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.json"]
+    }
+}
+
+// Sprite'y z opisu atlasu; nazwy zamieniamy na indeksy raz, przy wczytaniu opisu,
+// a gra wychodzi ze stanu Loading dopiero wtedy
+#[derive(Resource, Default)]
+pub struct Sprites {
+    pub atlas: Handle<TextureAtlas>,
+    meta: Handle<AtlasMeta>,
+    // Indeksy w kolejności Region::ALL
+    regions: [usize; Region::ALL.len()],
+    clips: HashMap<String, Clip>,
+    loaded: bool,
+}
+
+impl Sprites {
+    pub fn index(&self, region: Region) -> usize {
+        self.regions[region as usize]
+    }
+
+    // Czas klatki z opisu ma pierwszeństwo przed domyślnym z kodu
+    pub fn animation(&self, name: &str, default_seconds: Option<f32>) -> AnimationData {
+        let clip = self
+            .clips
+            .get(name)
+            .unwrap_or_else(|| panic!("clip '{name}' is not in REQUIRED_CLIPS"));
+        let data = AnimationData::from_frame_list(clip.frames.clone()).repeating();
+        match clip.frame_seconds.or(default_seconds) {
+            Some(seconds) => data.with_dur_sec(seconds),
+            None => data,
+        }
+    }
+}
+
+fn setup_sprites(mut sprites: ResMut<Sprites>, server: Res<AssetServer>) {
+    sprites.meta = server.load(ATLAS_META);
+    sprites.atlas = server.load(format!("{ATLAS_META}#atlas").as_str());
+}
+
+// Zmieniony opis działa dla nowo tworzonych sprite'ów, np. po przejściu do innego poziomu
+fn sync_sprites_system(
+    mut asset_events: EventReader<AssetEvent<AtlasMeta>>,
+    metas: Res<Assets<AtlasMeta>>,
+    mut sprites: ResMut<Sprites>,
+) {
+    for event in asset_events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+        if *handle != sprites.meta {
+            continue;
+        }
+        // Opis przeszedł AtlasMeta::validate w loaderze, więc wszystkie nazwy istnieją
+        if let Some(meta) = metas.get(handle) {
+            sprites.regions = Region::ALL.map(|region| meta.regions[region.name()]);
+            sprites.clips = meta.clips.clone();
+            sprites.loaded = true;
+        }
+    }
+}
+
+// Błędny opis atlasu zatrzymuje grę na tym ekranie, szczegóły są w logu loadera
+fn loading_system(
+    mut egui_context: ResMut<EguiContext>,
+    sprites: Res<Sprites>,
+    server: Res<AssetServer>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if sprites.loaded {
+        state.set(GameState::LevelSelect).unwrap();
+        return;
+    }
+    let failed = server.get_load_state(&sprites.meta) == LoadState::Failed;
+    egui::CentralPanel::default().show(egui_context.ctx_mut(), |ui| {
+        if failed {
+            ui.heading(format!("Failed to load {ATLAS_META}, see the log"));
+        } else {
+            ui.heading("Loading...");
+        }
+    });
+}
+
+impl Plugin for AtlasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AtlasMeta>()
+            .init_asset_loader::<AtlasMetaLoader>()
+            .init_resource::<Sprites>()
+            .add_startup_system(setup_sprites)
+            .add_system(sync_sprites_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading)
+                    .with_system(loading_system.after(sync_sprites_system)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    #[test]
+    fn shipped_atlas_names_all_sprites() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(ATLAS_META);
+        let meta: AtlasMeta = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        meta.validate().unwrap();
+        for state in ["idle", "walk"] {
+            for dir in ["down", "up", "left", "right"] {
+                let name = format!("player_{state}_{dir}");
+                assert!(REQUIRED_CLIPS.contains(&name.as_str()), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_frames_outside_grid() {
+        let json = r#"{"texture": "a.png", "tile_size": [16, 16], "columns": 2, "rows": 2,
+            "regions": {"box": 4}}"#;
+        let meta: AtlasMeta = serde_json::from_str(json).unwrap();
+        let err = meta.validate().unwrap_err();
+        assert!(err.message.contains("frame 4"), "{err}");
+    }
+
+    #[test]
+    fn rejects_missing_regions() {
+        let json = r#"{"texture": "a.png", "tile_size": [16, 16], "columns": 2, "rows": 2,
+            "regions": {"box": 3}}"#;
+        let meta: AtlasMeta = serde_json::from_str(json).unwrap();
+        let err = meta.validate().unwrap_err();
+        assert!(err.message.contains("is missing"), "{err}");
+    }
+}
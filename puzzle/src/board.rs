@@ -0,0 +1,209 @@
+use std::{ffi::OsStr, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{
+    atlas::{Region, Sprites},
+    editor::CUSTOM_LEVELS,
+    movement::GridTween,
+    sokoban::{Level, Tile},
+    xsb::{LevelCollection, XsbLoader},
+};
+
+pub const TILE_SIZE: f32 = 64.;
+
+const FLOOR_Z: f32 = 0.;
+const GOAL_Z: f32 = 1.;
+const CRATE_Z: f32 = 2.;
+pub const PLAYER_Z: f32 = 3.;
+
+pub struct BoardPlugin;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GridPos(pub IVec2);
+
+#[derive(Component)]
+pub struct Crate;
+
+#[derive(Component)]
+pub struct BoardTile;
+
+pub struct LevelSolvedEvent;
+
+// Wysyłane po wczytaniu poziomu, także po przeładowaniu zmienionego pliku
+pub struct LevelStartedEvent;
+
+#[derive(Resource, Default)]
+pub struct CurrentLevel {
+    pub collection: Handle<LevelCollection>,
+    pub index: usize,
+    // Poziom z edytora, grany zamiast poziomu z kolekcji
+    pub playtest: Option<Level>,
+}
+
+impl CurrentLevel {
+    // Poziomy z edytora, także już zapisane, wyników nie zapisują
+    pub fn record_key(&self, server: &AssetServer) -> Option<String> {
+        if self.playtest.is_some() {
+            return None;
+        }
+        let path = server.get_handle_path(&self.collection)?;
+        if path.path() == Path::new(CUSTOM_LEVELS) {
+            return None;
+        }
+        Some(level_key(path.path(), self.index))
+    }
+}
+
+// Klucz wyników w profilu, np. "puzzle.intro.3" dla trzeciego poziomu z levels/intro.xsb
+pub fn level_key(collection_path: &Path, index: usize) -> String {
+    let stem = collection_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+    format!("puzzle.{stem}.{}", index + 1)
+}
+
+// Wiersz 0 poziomu jest na górze ekranu, a cały poziom jest wyśrodkowany
+pub fn grid_to_world(level: &Level, pos: IVec2, z: f32) -> Vec3 {
+    Vec3::new(
+        (pos.x as f32 - (level.width - 1) as f32 / 2.) * TILE_SIZE,
+        ((level.height - 1) as f32 / 2. - pos.y as f32) * TILE_SIZE,
+        z,
+    )
+}
+
+pub fn world_to_grid(level: &Level, world: Vec2) -> IVec2 {
+    IVec2::new(
+        (world.x / TILE_SIZE + (level.width - 1) as f32 / 2.).round() as i32,
+        ((level.height - 1) as f32 / 2. - world.y / TILE_SIZE).round() as i32,
+    )
+}
+
+pub fn tile_sprite(sprites: &Sprites, region: Region, translation: Vec3) -> SpriteSheetBundle {
+    SpriteSheetBundle {
+        sprite: TextureAtlasSprite {
+            index: sprites.index(region),
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..default()
+        },
+        texture_atlas: sprites.atlas.clone(),
+        transform: Transform::from_translation(translation),
+        ..default()
+    }
+}
+
+fn load_level_system(
+    mut asset_events: EventReader<AssetEvent<LevelCollection>>,
+    collections: Res<Assets<LevelCollection>>,
+    current: Res<CurrentLevel>,
+    mut level: ResMut<Level>,
+    mut started_events: EventWriter<LevelStartedEvent>,
+) {
+    let reloaded = asset_events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            *handle == current.collection
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if !reloaded && !current.is_changed() {
+        return;
+    }
+    if let Some(playtest) = &current.playtest {
+        *level = playtest.clone();
+        started_events.send(LevelStartedEvent);
+        return;
+    }
+    let Some(collection) = collections.get(&current.collection) else {
+        return;
+    };
+    let Some(entry) = collection.levels.get(current.index) else {
+        error!("Level {} not found in collection", current.index);
+        return;
+    };
+    if let Some(title) = &entry.title {
+        info!("Level {}: {title}", current.index + 1);
+    }
+    *level = entry.level.clone();
+    started_events.send(LevelStartedEvent);
+}
+
+fn spawn_board_system(
+    mut commands: Commands,
+    mut started_events: EventReader<LevelStartedEvent>,
+    level: Res<Level>,
+    sprites: Res<Sprites>,
+    old_query: Query<Entity, Or<(With<BoardTile>, With<Crate>)>>,
+) {
+    if started_events.iter().last().is_none() {
+        return;
+    }
+    for entity in old_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for pos in level.positions() {
+        let tile = level.tile(pos);
+        let name = match tile {
+            Tile::Empty => continue,
+            Tile::Wall => Region::Wall,
+            Tile::Floor | Tile::Goal => Region::Floor,
+        };
+        commands.spawn((
+            BoardTile,
+            tile_sprite(&sprites, name, grid_to_world(&level, pos, FLOOR_Z)),
+        ));
+        if tile == Tile::Goal {
+            commands.spawn((
+                BoardTile,
+                tile_sprite(&sprites, Region::Goal, grid_to_world(&level, pos, GOAL_Z)),
+            ));
+        }
+    }
+
+    for &pos in level.boxes.iter() {
+        commands.spawn((
+            Crate,
+            GridPos(pos),
+            GridTween::default(),
+            tile_sprite(&sprites, Region::Box, grid_to_world(&level, pos, CRATE_Z)),
+        ));
+    }
+}
+
+pub fn move_crate<'a>(crates: impl Iterator<Item = Mut<'a, GridPos>>, from: IVec2, to: IVec2) {
+    for mut crate_pos in crates {
+        if crate_pos.0 == from {
+            crate_pos.0 = to;
+            return;
+        }
+    }
+}
+
+// Skrzynia na celu dostaje ciemniejszy kafelek
+fn crate_sprite_system(
+    level: Res<Level>,
+    sprites: Res<Sprites>,
+    mut query: Query<(&GridPos, &mut TextureAtlasSprite), (With<Crate>, Changed<GridPos>)>,
+) {
+    for (grid_pos, mut sprite) in query.iter_mut() {
+        sprite.index = match level.tile(grid_pos.0) {
+            Tile::Goal => sprites.index(Region::BoxOnGoal),
+            _ => sprites.index(Region::Box),
+        };
+    }
+}
+
+impl Plugin for BoardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelCollection>()
+            .init_asset_loader::<XsbLoader>()
+            .init_resource::<Level>()
+            .init_resource::<CurrentLevel>()
+            .add_event::<LevelSolvedEvent>()
+            .add_event::<LevelStartedEvent>()
+            .add_system(load_level_system)
+            .add_system(spawn_board_system.after(load_level_system))
+            .add_system(crate_sprite_system);
+    }
+}
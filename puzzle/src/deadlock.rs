@@ -0,0 +1,302 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::{
+    board::{Crate, GridPos, LevelStartedEvent},
+    history::HistoryCommand,
+    sokoban::{Level, Tile, DIRECTIONS},
+    GameState,
+};
+
+const DEADLOCK_COLOR: Color = Color::rgb(1., 0.45, 0.45);
+
+pub struct DeadlockPlugin;
+
+// Liczone raz po wczytaniu poziomu. Pole jest martwe, jeśli skrzynia z niego nie dojdzie
+// do żadnego celu nawet bez innych skrzyń; odległości solver używa też jako heurystyki
+#[derive(Resource, Clone, Debug, Default)]
+pub struct DeadSquares {
+    width: i32,
+    height: i32,
+    // Układ ścian i celów, z którego policzono odległości
+    tiles: Vec<Tile>,
+    goal_distance: Vec<Option<u32>>,
+}
+
+impl DeadSquares {
+    pub fn new(level: &Level) -> Self {
+        let mut squares = Self {
+            width: level.width,
+            height: level.height,
+            tiles: level.positions().map(|pos| level.tile(pos)).collect(),
+            goal_distance: vec![None; (level.width * level.height) as usize],
+        };
+
+        // Cofamy pchnięcia od celów: skrzynia mogła przyjść z sąsiedniego pola,
+        // jeśli za nim było miejsce dla gracza
+        let mut queue: VecDeque<IVec2> = level
+            .positions()
+            .filter(|&pos| level.tile(pos) == Tile::Goal)
+            .collect();
+        for &pos in queue.iter() {
+            squares.set_distance(pos, 0);
+        }
+        while let Some(pos) = queue.pop_front() {
+            let distance = squares.goal_distance(pos).unwrap_or_default();
+            for dir in DIRECTIONS {
+                let (from, stand) = (pos - dir, pos - dir * 2);
+                if level.tile(from).walkable()
+                    && level.tile(stand).walkable()
+                    && squares.goal_distance(from).is_none()
+                {
+                    squares.set_distance(from, distance + 1);
+                    queue.push_back(from);
+                }
+            }
+        }
+        squares
+    }
+
+    fn index(&self, pos: IVec2) -> Option<usize> {
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < self.width && pos.y < self.height;
+        inside.then(|| (pos.y * self.width + pos.x) as usize)
+    }
+
+    fn set_distance(&mut self, pos: IVec2, distance: u32) {
+        if let Some(index) = self.index(pos) {
+            self.goal_distance[index] = Some(distance);
+        }
+    }
+
+    // Najmniej pchnięć samotnej skrzyni z pola na dowolny cel
+    pub fn goal_distance(&self, pos: IVec2) -> Option<u32> {
+        self.goal_distance[self.index(pos)?]
+    }
+
+    // Ściany i pola poza poziomem też liczą się jako martwe
+    pub fn is_dead(&self, pos: IVec2) -> bool {
+        self.goal_distance(pos).is_none()
+    }
+
+    // Pola policzone dla poziomu o tych samych wymiarach, ścianach i celach
+    pub fn fits(&self, level: &Level) -> bool {
+        self.width == level.width
+            && self.height == level.height
+            && level
+                .positions()
+                .map(|pos| level.tile(pos))
+                .eq(self.tiles.iter().copied())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeadlockKind {
+    Corner,
+    DeadSquare,
+    Frozen,
+}
+
+#[derive(Resource, Default)]
+pub struct Deadlocks {
+    pub boxes: Vec<(IVec2, DeadlockKind)>,
+}
+
+fn is_wall(level: &Level, pos: IVec2) -> bool {
+    !level.tile(pos).walkable()
+}
+
+// Skrzynia jest zamrożona, gdy nie ruszy się w żadnej osi. Sąsiednie skrzynie sprawdzamy
+// rekurencyjnie, a te już sprawdzane traktujemy jak ściany
+fn is_frozen(level: &Level, dead: &DeadSquares, pos: IVec2, visiting: &mut HashSet<IVec2>) -> bool {
+    visiting.insert(pos);
+    [IVec2::X, IVec2::Y].into_iter().all(|axis| {
+        let sides = [pos - axis, pos + axis];
+        sides
+            .iter()
+            .any(|&side| is_wall(level, side) || visiting.contains(&side))
+            || sides.iter().all(|&side| dead.is_dead(side))
+            || sides
+                .iter()
+                .any(|&side| level.has_box(side) && is_frozen(level, dead, side, visiting))
+    })
+}
+
+// Skrzynie, których już nie da się dostawić na cele; zamrożona grupa jest zgłaszana cała,
+// także ze skrzyniami stojącymi na celach
+pub fn find_deadlocks(level: &Level, dead: &DeadSquares) -> Vec<(IVec2, DeadlockKind)> {
+    let mut boxes: Vec<IVec2> = level.boxes.iter().copied().collect();
+    boxes.sort_by_key(|pos| (pos.y, pos.x));
+
+    let mut found = Vec::new();
+    let mut frozen = Vec::new();
+    for &pos in boxes.iter() {
+        if level.tile(pos) == Tile::Goal {
+            continue;
+        }
+        let blocked = |axis: IVec2| is_wall(level, pos - axis) || is_wall(level, pos + axis);
+        if blocked(IVec2::X) && blocked(IVec2::Y) {
+            found.push((pos, DeadlockKind::Corner));
+        } else if dead.is_dead(pos) {
+            found.push((pos, DeadlockKind::DeadSquare));
+        } else if is_frozen(level, dead, pos, &mut HashSet::new()) {
+            frozen.push(pos);
+        }
+    }
+
+    let mut cluster: HashSet<IVec2> = frozen.iter().copied().collect();
+    while let Some(pos) = frozen.pop() {
+        for dir in DIRECTIONS {
+            let next = pos + dir;
+            if level.has_box(next)
+                && !cluster.contains(&next)
+                && is_frozen(level, dead, next, &mut HashSet::new())
+            {
+                cluster.insert(next);
+                frozen.push(next);
+            }
+        }
+    }
+    for pos in boxes {
+        if cluster.contains(&pos) && !found.iter().any(|&(found_pos, _)| found_pos == pos) {
+            found.push((pos, DeadlockKind::Frozen));
+        }
+    }
+    found
+}
+
+pub fn dead_squares_system(
+    mut started_events: EventReader<LevelStartedEvent>,
+    level: Res<Level>,
+    mut dead: ResMut<DeadSquares>,
+) {
+    if started_events.iter().last().is_some() {
+        *dead = DeadSquares::new(&level);
+    }
+}
+
+fn detect_deadlocks_system(
+    level: Res<Level>,
+    dead: Res<DeadSquares>,
+    mut deadlocks: ResMut<Deadlocks>,
+) {
+    if !level.is_changed() && !dead.is_changed() {
+        return;
+    }
+    let found = find_deadlocks(&level, &dead);
+    if found != deadlocks.boxes {
+        if let Some((pos, kind)) = found.first() {
+            info!("Deadlock ({kind:?}) at box {pos}");
+        }
+        deadlocks.boxes = found;
+    }
+}
+
+fn highlight_deadlocks_system(
+    deadlocks: Res<Deadlocks>,
+    mut crate_query: Query<(&GridPos, &mut TextureAtlasSprite), With<Crate>>,
+) {
+    if !deadlocks.is_changed() {
+        return;
+    }
+    for (grid_pos, mut sprite) in crate_query.iter_mut() {
+        if deadlocks.boxes.iter().any(|&(pos, _)| pos == grid_pos.0) {
+            sprite.color = DEADLOCK_COLOR;
+        } else if sprite.color == DEADLOCK_COLOR {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
+
+fn draw_deadlock_warning(
+    mut egui_context: ResMut<EguiContext>,
+    deadlocks: Res<Deadlocks>,
+    mut history_commands: EventWriter<HistoryCommand>,
+) {
+    let Some(&(_, kind)) = deadlocks.boxes.first() else {
+        return;
+    };
+    let reason = match kind {
+        DeadlockKind::Corner => "A box is stuck in a corner.",
+        DeadlockKind::DeadSquare => "A box can no longer reach any goal.",
+        DeadlockKind::Frozen => "Boxes are jammed against each other.",
+    };
+
+    egui::Window::new("Deadlock")
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0., 10.))
+        .title_bar(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.strong("Deadlock!");
+            ui.label(reason);
+            if ui.button("Undo (Z)").clicked() {
+                history_commands.send(HistoryCommand::Undo);
+            }
+        });
+}
+
+impl Plugin for DeadlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeadSquares>()
+            .init_resource::<Deadlocks>()
+            .add_system(dead_squares_system)
+            .add_system(detect_deadlocks_system.after(dead_squares_system))
+            .add_system(highlight_deadlocks_system.after(detect_deadlocks_system))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(draw_deadlock_warning),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deadlocks(rows: &[&str]) -> Vec<(IVec2, DeadlockKind)> {
+        let level = Level::from_rows(rows);
+        find_deadlocks(&level, &DeadSquares::new(&level))
+    }
+
+    #[test]
+    fn finds_dead_squares() {
+        let level = Level::from_rows(&["######", "#@   #", "# $ .#", "######"]);
+        let dead = DeadSquares::new(&level);
+        assert_eq!(dead.goal_distance(IVec2::new(2, 2)), Some(2));
+        assert!(dead.is_dead(IVec2::new(2, 1)));
+        assert!(dead.is_dead(IVec2::new(0, 0)));
+    }
+
+    #[test]
+    fn fits_only_the_same_walls_and_goals() {
+        let level = Level::from_rows(&["######", "#@ $.#", "######"]);
+        let dead = DeadSquares::new(&level);
+        assert!(dead.fits(&Level::from_rows(&["######", "# @$.#", "######"])));
+        assert!(!dead.fits(&Level::from_rows(&["######", "#@$#.#", "######"])));
+        assert!(!dead.fits(&Level::from_rows(&["######", "#.$@ #", "######"])));
+    }
+
+    #[test]
+    fn reports_corner_and_dead_square_boxes() {
+        let found = deadlocks(&["######", "#$@  #", "#   .#", "######"]);
+        assert_eq!(found, [(IVec2::new(1, 1), DeadlockKind::Corner)]);
+
+        let found = deadlocks(&["######", "# @$ #", "#   .#", "######"]);
+        assert_eq!(found, [(IVec2::new(3, 1), DeadlockKind::DeadSquare)]);
+    }
+
+    #[test]
+    fn reports_whole_frozen_cluster() {
+        let rows = [
+            "#######", "#     #", "# $$  #", "# *$ @#", "#   ..#", "#######",
+        ];
+        let found = deadlocks(&rows);
+        assert!(found.iter().all(|&(_, kind)| kind == DeadlockKind::Frozen));
+        assert_eq!(found.len(), 4);
+
+        assert!(
+            deadlocks(&["#######", "#     #", "# $   #", "# *$ @#", "#   ..#", "#######"])
+                .is_empty()
+        );
+    }
+}
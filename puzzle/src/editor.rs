@@ -0,0 +1,474 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+
+use crate::{
+    atlas::{Region, Sprites},
+    board::{grid_to_world, tile_sprite, world_to_grid, CurrentLevel, LevelStartedEvent},
+    deadlock::{dead_squares_system, DeadSquares},
+    packs::LevelPacks,
+    sokoban::{Level, Tile},
+    solver::{LevelVerifiedEvent, Solver, SolverGoal},
+    xsb::{parse_collection, write_level, LevelCollection, XsbError},
+    GameState,
+};
+
+const EDITOR_KEY: KeyCode = KeyCode::F2;
+const EXIT_KEY: KeyCode = KeyCode::Escape;
+const VERIFY_KEY: KeyCode = KeyCode::V;
+const SAVE_KEY: KeyCode = KeyCode::F5;
+// Poza katalogiem levels, więc zapisane poziomy nie mieszają się z poziomami gry
+pub const CUSTOM_LEVELS: &str = "custom/custom.xsb";
+const SAVE_TITLE: &str = "Custom level";
+
+const DEAD_SQUARE_Z: f32 = 0.5;
+const CURSOR_Z: f32 = 5.;
+const CURSOR_COLOR: Color = Color::rgba(1., 1., 1., 0.6);
+const ERASE_COLOR: Color = Color::rgba(1., 0.3, 0.3, 0.6);
+const DEAD_SQUARE_COLOR: Color = Color::rgba(0.6, 0., 0., 0.35);
+
+const MIN_SIZE: i32 = 3;
+const MAX_SIZE: i32 = 40;
+
+pub struct EditorPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Brush {
+    Wall,
+    Floor,
+    Goal,
+    Box,
+    Player,
+    Erase,
+}
+
+impl Brush {
+    fn sprite(self) -> Region {
+        match self {
+            Brush::Wall => Region::Wall,
+            Brush::Floor | Brush::Erase => Region::Floor,
+            Brush::Goal => Region::Goal,
+            Brush::Box => Region::Box,
+            Brush::Player => Region::Player,
+        }
+    }
+}
+
+const BRUSH_KEYS: [(KeyCode, Brush); 6] = [
+    (KeyCode::Key1, Brush::Wall),
+    (KeyCode::Key2, Brush::Floor),
+    (KeyCode::Key3, Brush::Goal),
+    (KeyCode::Key4, Brush::Box),
+    (KeyCode::Key5, Brush::Player),
+    (KeyCode::Key6, Brush::Erase),
+];
+
+// Szerokość zmieniamy klawiszami [ ], wysokość - =
+const RESIZE_KEYS: [(KeyCode, IVec2); 4] = [
+    (KeyCode::LBracket, IVec2::NEG_X),
+    (KeyCode::RBracket, IVec2::X),
+    (KeyCode::Minus, IVec2::NEG_Y),
+    (KeyCode::Equals, IVec2::Y),
+];
+
+#[derive(Resource)]
+struct Editor {
+    brush: Brush,
+    // Ostatni poziom sprawdzony solverem; zapisać można tylko taki
+    verified: Option<Level>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            brush: Brush::Wall,
+            verified: None,
+        }
+    }
+}
+
+pub fn custom_levels_path() -> PathBuf {
+    FileAssetIo::get_base_path()
+        .join("assets")
+        .join(CUSTOM_LEVELS)
+}
+
+#[derive(Component)]
+struct BrushCursor;
+
+#[derive(Component)]
+struct DeadSquareMarker;
+
+// Gracz i skrzynie stoją zawsze na podłodze albo celu, a pola gracza nie da się zamurować
+fn paint(level: &mut Level, pos: IVec2, brush: Brush) {
+    let occupied = pos == level.player;
+    match brush {
+        Brush::Wall | Brush::Erase | Brush::Box if occupied => {}
+        Brush::Wall => {
+            level.set_tile(pos, Tile::Wall);
+            level.boxes.remove(&pos);
+        }
+        Brush::Erase => {
+            level.set_tile(pos, Tile::Empty);
+            level.boxes.remove(&pos);
+        }
+        Brush::Floor => {
+            level.set_tile(pos, Tile::Floor);
+            level.boxes.remove(&pos);
+        }
+        Brush::Goal => level.set_tile(pos, Tile::Goal),
+        Brush::Box | Brush::Player => {
+            if !level.tile(pos).walkable() {
+                level.set_tile(pos, Tile::Floor);
+            }
+            if brush == Brush::Box {
+                level.boxes.insert(pos);
+            } else {
+                level.boxes.remove(&pos);
+                level.player = pos;
+            }
+        }
+    }
+}
+
+// Poziom edytora musi dać się zapisać i wczytać z powrotem, tak jak poziomy z kolekcji
+fn validate(level: &Level) -> Result<(), XsbError> {
+    parse_collection(&write_level(level)).map(|_| ())
+}
+
+// Poziom trafia na koniec kolekcji pod kolejnym numerem. None, jeśli kolekcja już go zawiera;
+// błąd, jeśli istniejącego pliku nie da się wczytać - wtedy niczego nie nadpisujemy
+fn append_level(existing: &str, level: &Level) -> Result<Option<String>, XsbError> {
+    let board = write_level(level);
+    let count = if existing.trim().is_empty() {
+        0
+    } else {
+        let collection = parse_collection(existing)?;
+        if collection
+            .levels
+            .iter()
+            .any(|entry| write_level(&entry.level) == board)
+        {
+            return Ok(None);
+        }
+        collection.levels.len()
+    };
+
+    let mut text = existing.trim_end().to_string();
+    if !text.is_empty() {
+        text.push_str("\n\n");
+    }
+    text.push_str(&format!("Title: {SAVE_TITLE} {}\n{board}", count + 1));
+    Ok(Some(text))
+}
+
+// Kamera 2D nie jest skalowana, więc środek okna to pozycja kamery
+fn cursor_cell(
+    windows: &Windows,
+    camera_query: &Query<&GlobalTransform, With<Camera>>,
+    level: &Level,
+) -> Option<IVec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let camera = camera_query.get_single().ok()?;
+    let size = Vec2::new(window.width(), window.height());
+    let pos = world_to_grid(level, camera.translation().truncate() + cursor - size / 2.);
+    level.contains(pos).then_some(pos)
+}
+
+// Edytor zaczyna od poziomu w stanie początkowym, a wyjście z niego od razu uruchamia test
+fn toggle_editor_system(
+    input: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+    mut level: ResMut<Level>,
+    mut current: ResMut<CurrentLevel>,
+    collections: Res<Assets<LevelCollection>>,
+    mut started_events: EventWriter<LevelStartedEvent>,
+) {
+    match state.current() {
+        GameState::Playing if input.just_pressed(EDITOR_KEY) => {
+            let start = current.playtest.clone().or_else(|| {
+                let collection = collections.get(&current.collection)?;
+                Some(collection.levels.get(current.index)?.level.clone())
+            });
+            if let Some(start) = start {
+                *level = start;
+            }
+            started_events.send(LevelStartedEvent);
+            state.set(GameState::Editing).unwrap();
+            info!("Editor: 1-6 brushes, [ ] - = resize, V verify, F5 save, F2 playtest");
+        }
+        GameState::Editing if input.just_pressed(EDITOR_KEY) => {
+            current.playtest = Some(level.clone());
+            state.set(GameState::Playing).unwrap();
+        }
+        GameState::Editing if input.just_pressed(EXIT_KEY) => {
+            current.playtest = None;
+            state.set(GameState::Playing).unwrap();
+        }
+        _ => {}
+    }
+}
+
+fn brush_system(input: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    for &(key, brush) in BRUSH_KEYS.iter() {
+        if input.just_pressed(key) {
+            editor.brush = brush;
+            info!("Brush: {brush:?}");
+        }
+    }
+}
+
+// Lewy przycisk maluje wybranym pędzlem, prawy wymazuje
+fn paint_system(
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    editor: Res<Editor>,
+    mut level: ResMut<Level>,
+    mut started_events: EventWriter<LevelStartedEvent>,
+) {
+    let brush = if mouse.pressed(MouseButton::Right) {
+        Brush::Erase
+    } else if mouse.pressed(MouseButton::Left) {
+        editor.brush
+    } else {
+        return;
+    };
+    let Some(pos) = cursor_cell(&windows, &camera_query, &level) else {
+        return;
+    };
+    let mut edited = level.clone();
+    paint(&mut edited, pos, brush);
+    if edited != *level {
+        *level = edited;
+        started_events.send(LevelStartedEvent);
+    }
+}
+
+fn resize_system(
+    input: Res<Input<KeyCode>>,
+    mut level: ResMut<Level>,
+    mut started_events: EventWriter<LevelStartedEvent>,
+) {
+    let change = RESIZE_KEYS
+        .iter()
+        .filter(|(key, _)| input.just_pressed(*key))
+        .fold(IVec2::ZERO, |change, (_, dir)| change + *dir);
+    if change == IVec2::ZERO {
+        return;
+    }
+    let size = IVec2::new(level.width, level.height);
+    let resized = (size + change).clamp(IVec2::splat(MIN_SIZE), IVec2::splat(MAX_SIZE));
+    if resized == size {
+        return;
+    }
+    level.resize(resized.x, resized.y);
+    started_events.send(LevelStartedEvent);
+    info!("Level size: {}x{}", resized.x, resized.y);
+}
+
+fn verify_system(
+    input: Res<Input<KeyCode>>,
+    level: Res<Level>,
+    dead: Res<DeadSquares>,
+    mut solver: ResMut<Solver>,
+) {
+    if !input.just_pressed(VERIFY_KEY) {
+        return;
+    }
+    if let Err(err) = validate(&level) {
+        warn!("Level is not valid: {err}");
+        return;
+    }
+    info!("Verifying level...");
+    solver.request(SolverGoal::Verify, &level, &dead);
+}
+
+fn verified_system(
+    mut verified_events: EventReader<LevelVerifiedEvent>,
+    mut editor: ResMut<Editor>,
+) {
+    if let Some(LevelVerifiedEvent(level)) = verified_events.iter().last() {
+        editor.verified = Some(level.clone());
+    }
+}
+
+// Zapisany poziom jest dopisywany do kolekcji i pojawia się na liście poziomów;
+// plik jest obserwowany, więc kolejny zapis od razu trafia do gry
+fn save_system(
+    input: Res<Input<KeyCode>>,
+    level: Res<Level>,
+    editor: Res<Editor>,
+    server: Res<AssetServer>,
+    mut packs: ResMut<LevelPacks>,
+) {
+    if !input.just_pressed(SAVE_KEY) {
+        return;
+    }
+    if editor.verified.as_ref() != Some(&*level) {
+        warn!("Cannot save level: verify it first (V)");
+        return;
+    }
+    let path = custom_levels_path();
+    let existing = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            error!("Failed to read {}: {err}", path.display());
+            return;
+        }
+    };
+    let text = match append_level(&existing, &level) {
+        Ok(Some(text)) => text,
+        Ok(None) => {
+            info!("Level is already saved in {}", path.display());
+            return;
+        }
+        Err(err) => {
+            error!("Not saving level, {} is invalid: {err}", path.display());
+            return;
+        }
+    };
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, text));
+    match saved {
+        Ok(()) => {
+            info!("Level saved to {}", path.display());
+            packs.custom = Some(server.load(CUSTOM_LEVELS));
+        }
+        Err(err) => error!("Failed to save level to {}: {err}", path.display()),
+    }
+}
+
+fn spawn_cursor(mut commands: Commands, sprites: Res<Sprites>) {
+    let mut cursor = tile_sprite(&sprites, Region::Floor, Vec3::Z * CURSOR_Z);
+    cursor.visibility.is_visible = false;
+    commands.spawn((BrushCursor, cursor));
+}
+
+fn despawn_cursor(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<BrushCursor>, With<DeadSquareMarker>)>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Podgląd pędzla pod kursorem, z tymi samymi kafelkami co plansza
+fn cursor_system(
+    windows: Res<Windows>,
+    camera_query: Query<&GlobalTransform, With<Camera>>,
+    editor: Res<Editor>,
+    level: Res<Level>,
+    sprites: Res<Sprites>,
+    mut query: Query<(&mut Transform, &mut TextureAtlasSprite, &mut Visibility), With<BrushCursor>>,
+) {
+    let pos = cursor_cell(&windows, &camera_query, &level);
+    for (mut transform, mut sprite, mut visibility) in query.iter_mut() {
+        visibility.is_visible = pos.is_some();
+        let Some(pos) = pos else {
+            continue;
+        };
+        transform.translation = grid_to_world(&level, pos, CURSOR_Z);
+        sprite.index = sprites.index(editor.brush.sprite());
+        sprite.color = if editor.brush == Brush::Erase {
+            ERASE_COLOR
+        } else {
+            CURSOR_COLOR
+        };
+    }
+}
+
+// Pola, z których skrzynia nie dojdzie do celu; projektant widzi je od razu przy malowaniu
+fn dead_square_overlay_system(
+    mut commands: Commands,
+    level: Res<Level>,
+    dead: Res<DeadSquares>,
+    sprites: Res<Sprites>,
+    marker_query: Query<Entity, With<DeadSquareMarker>>,
+) {
+    if !dead.is_changed() {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for pos in level.positions() {
+        if level.tile(pos) == Tile::Floor && dead.is_dead(pos) {
+            let mut marker = tile_sprite(
+                &sprites,
+                Region::Floor,
+                grid_to_world(&level, pos, DEAD_SQUARE_Z),
+            );
+            marker.sprite.color = DEAD_SQUARE_COLOR;
+            commands.spawn((DeadSquareMarker, marker));
+        }
+    }
+}
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>()
+            .add_system(toggle_editor_system)
+            .add_system_set(SystemSet::on_enter(GameState::Editing).with_system(spawn_cursor))
+            .add_system_set(SystemSet::on_exit(GameState::Editing).with_system(despawn_cursor))
+            .add_system_set(
+                SystemSet::on_update(GameState::Editing)
+                    .with_system(brush_system)
+                    // Zmiana poziomu musi trafić do martwych pól w tej samej klatce
+                    .with_system(paint_system.after(brush_system).before(dead_squares_system))
+                    .with_system(resize_system.before(dead_squares_system))
+                    .with_system(verify_system.after(dead_squares_system))
+                    .with_system(verified_system)
+                    .with_system(save_system.after(verified_system))
+                    .with_system(cursor_system.after(paint_system))
+                    .with_system(dead_square_overlay_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn painting_keeps_player_on_floor() {
+        let mut level = Level::from_rows(&["#####", "#@ .#", "#####"]);
+        let start = IVec2::new(1, 1);
+        paint(&mut level, start, Brush::Wall);
+        assert_eq!(level.tile(start), Tile::Floor);
+
+        paint(&mut level, IVec2::new(2, 1), Brush::Box);
+        paint(&mut level, IVec2::new(4, 1), Brush::Player);
+        assert_eq!(level.player, IVec2::new(4, 1));
+        assert_eq!(level.tile(IVec2::new(4, 1)), Tile::Floor);
+        assert!(level.has_box(IVec2::new(2, 1)));
+    }
+
+    #[test]
+    fn saving_appends_numbered_levels_once() {
+        let first = Level::from_rows(&["#####", "#@$.#", "#####"]);
+        let second = Level::from_rows(&["######", "#@ $.#", "######"]);
+
+        let text = append_level("", &first).unwrap().unwrap();
+        let text = append_level(&text, &second).unwrap().unwrap();
+        assert_eq!(append_level(&text, &second).unwrap(), None);
+
+        let collection = parse_collection(&text).unwrap();
+        let titles: Vec<_> = collection
+            .levels
+            .iter()
+            .map(|entry| entry.title.as_deref())
+            .collect();
+        assert_eq!(titles, [Some("Custom level 1"), Some("Custom level 2")]);
+        assert_eq!(
+            write_level(&collection.levels[1].level),
+            write_level(&second)
+        );
+        assert!(append_level("#@", &first).is_err());
+    }
+}
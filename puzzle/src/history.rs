@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+
+use crate::{
+    animation::AnimationStateMachine,
+    board::{move_crate, Crate, CurrentLevel, GridPos, LevelSolvedEvent, LevelStartedEvent},
+    movement::{snap_to_grid, start_tween_system, tween_system},
+    sokoban::{Level, Step},
+    GameState, Player, PlayerDir, PlayerState,
+};
+
+const UNDO_KEYS: [KeyCode; 2] = [KeyCode::Z, KeyCode::Back];
+const REDO_KEYS: [KeyCode; 1] = [KeyCode::Y];
+const RESTART_KEY: KeyCode = KeyCode::R;
+
+pub struct HistoryPlugin;
+
+// Ruch razem z kierunkiem, w którym gracz patrzył przed nim
+#[derive(Clone, Copy, Debug)]
+pub struct HistoryEntry {
+    pub step: Step,
+    pub facing_before: PlayerDir,
+    pub facing_after: PlayerDir,
+}
+
+#[derive(Resource, Default)]
+pub struct History {
+    done: Vec<HistoryEntry>,
+    undone: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn record(&mut self, entry: HistoryEntry) {
+        self.done.push(entry);
+        self.undone.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    // Cofnięte ruchy się nie liczą
+    pub fn moves(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn pushes(&self) -> usize {
+        self.done
+            .iter()
+            .filter(|entry| entry.step.pushed.is_some())
+            .count()
+    }
+
+    fn undo(&mut self) -> Option<HistoryEntry> {
+        let entry = self.done.pop()?;
+        self.undone.push(entry);
+        Some(entry)
+    }
+
+    fn redo(&mut self) -> Option<HistoryEntry> {
+        let entry = self.undone.pop()?;
+        self.done.push(entry);
+        Some(entry)
+    }
+}
+
+fn clear_history_system(
+    mut started_events: EventReader<LevelStartedEvent>,
+    mut history: ResMut<History>,
+) {
+    if started_events.iter().last().is_some() {
+        history.clear();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HistoryCommand {
+    Undo,
+    Redo,
+    Restart,
+}
+
+fn history_input_system(
+    input: Res<Input<KeyCode>>,
+    mut history_commands: EventWriter<HistoryCommand>,
+) {
+    if input.just_pressed(RESTART_KEY) {
+        history_commands.send(HistoryCommand::Restart);
+    } else if input.any_just_pressed(UNDO_KEYS) {
+        history_commands.send(HistoryCommand::Undo);
+    } else if input.any_just_pressed(REDO_KEYS) {
+        history_commands.send(HistoryCommand::Redo);
+    }
+}
+
+// Cofnięcie ustawia pozycje od razu, więc ewentualne przejście w toku jest przerywane
+fn apply_history_system(
+    mut history_commands: EventReader<HistoryCommand>,
+    mut history: ResMut<History>,
+    mut level: ResMut<Level>,
+    mut current: ResMut<CurrentLevel>,
+    mut player_query: Query<
+        (
+            &mut GridPos,
+            &mut Transform,
+            &mut AnimationStateMachine<(PlayerDir, PlayerState)>,
+        ),
+        With<Player>,
+    >,
+    mut crate_query: Query<(&mut GridPos, &mut Transform), (With<Crate>, Without<Player>)>,
+    mut solved_events: EventWriter<LevelSolvedEvent>,
+) {
+    for &command in history_commands.iter() {
+        let (entry, undo) = match command {
+            HistoryCommand::Restart => {
+                // Ponowne wczytanie poziomu; historia czyści się na LevelStartedEvent
+                current.set_changed();
+                return;
+            }
+            HistoryCommand::Undo => (history.undo(), true),
+            HistoryCommand::Redo => (history.redo(), false),
+        };
+        let Some(entry) = entry else {
+            continue;
+        };
+
+        let step = entry.step;
+        let (player_pos, facing) = if undo {
+            level.undo_step(&step);
+            if let Some((box_from, box_to)) = step.pushed {
+                move_crate(crate_query.iter_mut().map(|(pos, _)| pos), box_to, box_from);
+            }
+            (step.from, entry.facing_before)
+        } else {
+            level.redo_step(&step);
+            if let Some((box_from, box_to)) = step.pushed {
+                move_crate(crate_query.iter_mut().map(|(pos, _)| pos), box_from, box_to);
+            }
+            (step.to, entry.facing_after)
+        };
+
+        for (mut grid_pos, mut transform, mut anim) in player_query.iter_mut() {
+            grid_pos.0 = player_pos;
+            snap_to_grid(&level, &grid_pos, &mut transform);
+            anim.switch_state((facing, PlayerState::Idle));
+        }
+        for (grid_pos, mut transform) in crate_query.iter_mut() {
+            snap_to_grid(&level, &grid_pos, &mut transform);
+        }
+
+        if !undo && level.is_solved() {
+            solved_events.send(LevelSolvedEvent);
+        }
+    }
+}
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_event::<HistoryCommand>()
+            .add_system(clear_history_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(history_input_system)
+                    .with_system(
+                        apply_history_system
+                            .after(history_input_system)
+                            .after(clear_history_system)
+                            .after(tween_system)
+                            .before(start_tween_system),
+                    ),
+            );
+    }
+}
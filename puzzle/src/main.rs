@@ -1,21 +1,53 @@
 mod animation;
+mod atlas;
+mod board;
+mod deadlock;
+mod editor;
+mod history;
+mod movement;
+mod packs;
+mod score;
+mod sokoban;
+mod solver;
+mod xsb;
 
-use std::{collections::HashMap, hash::Hash};
-
-use animation::{
-    advance_animation_system, apply_animation_system, AnimationData, AnimationStateMachine,
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
 };
+
+use animation::{advance_animation_system, apply_animation_system, AnimationStateMachine};
+use atlas::{AtlasPlugin, Region, Sprites};
 use bevy::prelude::*;
+use bevy_egui::EguiPlugin;
+use board::{
+    grid_to_world, move_crate, BoardPlugin, Crate, GridPos, LevelSolvedEvent, LevelStartedEvent,
+    PLAYER_Z, TILE_SIZE,
+};
+use deadlock::DeadlockPlugin;
+use editor::EditorPlugin;
+use history::{History, HistoryEntry, HistoryPlugin};
+use movement::{
+    snap_to_grid, start_tween_system, tween_system, GridTween, MovementPlugin, MovementSettings,
+};
+use packs::PacksPlugin;
+use profiles::ProfilesPlugin;
+use score::ScorePlugin;
+use sokoban::Level;
+use solver::SolverPlugin;
 
 #[derive(Component)]
 struct Player;
 
-#[derive(Resource, Default)]
-struct MainAtlas {
-    handle: Handle<TextureAtlas>,
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum GameState {
+    Loading,
+    LevelSelect,
+    Playing,
+    Editing,
 }
 
-#[derive(Hash, Eq, PartialEq, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
 enum PlayerDir {
     Up,
     Down,
@@ -23,142 +55,275 @@ enum PlayerDir {
     Right,
 }
 
+impl PlayerDir {
+    // Wiersze poziomu rosną w dół ekranu
+    fn offset(self) -> IVec2 {
+        match self {
+            PlayerDir::Up => IVec2::NEG_Y,
+            PlayerDir::Down => IVec2::Y,
+            PlayerDir::Left => IVec2::NEG_X,
+            PlayerDir::Right => IVec2::X,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PlayerDir::Up => "up",
+            PlayerDir::Down => "down",
+            PlayerDir::Left => "left",
+            PlayerDir::Right => "right",
+        }
+    }
+
+    fn from_offset(offset: IVec2) -> Option<Self> {
+        [
+            PlayerDir::Up,
+            PlayerDir::Down,
+            PlayerDir::Left,
+            PlayerDir::Right,
+        ]
+        .into_iter()
+        .find(|dir| dir.offset() == offset)
+    }
+}
+
+// Prośba o jeden krok gracza - z klawiatury albo z odtwarzanego rozwiązania
+struct MoveEvent(PlayerDir);
+
+const DIRECTION_KEYS: [(PlayerDir, [KeyCode; 2]); 4] = [
+    (PlayerDir::Up, [KeyCode::W, KeyCode::Up]),
+    (PlayerDir::Down, [KeyCode::S, KeyCode::Down]),
+    (PlayerDir::Left, [KeyCode::A, KeyCode::Left]),
+    (PlayerDir::Right, [KeyCode::D, KeyCode::Right]),
+];
+
+// Wciśnięcia czekające na koniec przejścia oraz przytrzymany kierunek
+#[derive(Resource, Default)]
+struct MoveInput {
+    buffered: VecDeque<PlayerDir>,
+    held: Option<PlayerDir>,
+    held_for: f32,
+}
+
 #[derive(Hash, Eq, PartialEq, Clone, Copy)]
 enum PlayerState {
     Walk,
     Idle,
 }
 
+impl PlayerState {
+    fn name(self) -> &'static str {
+        match self {
+            PlayerState::Walk => "walk",
+            PlayerState::Idle => "idle",
+        }
+    }
+}
+
 fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
-fn setup_assets(
-    server: Res<AssetServer>,
-    mut main_atlas: ResMut<MainAtlas>,
-    mut atlases: ResMut<Assets<TextureAtlas>>,
+// Klipy "player_<stan>_<kierunek>" z opisu atlasu. Klatka chodu zmienia się co krok,
+// więc nogi nadążają za przejściem między polami
+fn setup_player(
+    mut commands: Commands,
+    sprites: Res<Sprites>,
+    level: Res<Level>,
+    settings: Res<MovementSettings>,
 ) {
-    let texture_handle = server.load("atlases/sokoban.png");
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(128., 128.), 13, 8, None, None);
-
-    let atlas_handle = atlases.add(texture_atlas);
-
-    main_atlas.handle = atlas_handle;
-}
-
-fn setup_player(mut commands: Commands, main_atlas: Res<MainAtlas>) {
+    let clips = DIRECTION_KEYS.iter().flat_map(|&(dir, _)| {
+        [PlayerState::Walk, PlayerState::Idle].map(|state| {
+            // Chód bez czasu klatki w opisie atlasu idzie w rytm kroku
+            let default_seconds = match state {
+                PlayerState::Walk => Some(settings.step_seconds),
+                PlayerState::Idle => None,
+            };
+            let name = format!("player_{}_{}", state.name(), dir.name());
+            ((dir, state), sprites.animation(&name, default_seconds))
+        })
+    });
     commands.spawn((
         Player,
+        GridPos(level.player),
+        GridTween::default(),
         SpriteSheetBundle {
-            texture_atlas: main_atlas.handle.clone(),
+            sprite: TextureAtlasSprite {
+                index: sprites.index(Region::Player),
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            texture_atlas: sprites.atlas.clone(),
+            transform: Transform::from_translation(grid_to_world(&level, level.player, PLAYER_Z)),
             ..default()
         },
-        AnimationStateMachine::from_data(
-            [
-                (
-                    (PlayerDir::Down, PlayerState::Walk),
-                    AnimationData::from_frames(53, 2)
-                        .with_dur_sec(0.2)
-                        .repeating(),
-                ),
-                (
-                    (PlayerDir::Up, PlayerState::Walk),
-                    AnimationData::from_frames(56, 2)
-                        .with_dur_sec(0.2)
-                        .repeating(),
-                ),
-                (
-                    (PlayerDir::Right, PlayerState::Walk),
-                    AnimationData::from_frames(79, 2)
-                        .with_dur_sec(0.2)
-                        .repeating(),
-                ),
-                (
-                    (PlayerDir::Left, PlayerState::Walk),
-                    AnimationData::from_frames(82, 2)
-                        .with_dur_sec(0.2)
-                        .repeating(),
-                ),
-                (
-                    (PlayerDir::Down, PlayerState::Idle),
-                    AnimationData::from_frames(52, 1).repeating(),
-                ),
-                (
-                    (PlayerDir::Up, PlayerState::Idle),
-                    AnimationData::from_frames(55, 1).repeating(),
-                ),
-                (
-                    (PlayerDir::Right, PlayerState::Idle),
-                    AnimationData::from_frames(78, 1).repeating(),
-                ),
-                (
-                    (PlayerDir::Left, PlayerState::Idle),
-                    AnimationData::from_frames(81, 1),
-                ),
-            ],
-            (PlayerDir::Down, PlayerState::Idle),
-        ),
+        AnimationStateMachine::from_data(clips, (PlayerDir::Down, PlayerState::Idle)),
     ));
 }
 
-fn player(
-    mut query: Query<(
-        &mut Transform,
-        &mut AnimationStateMachine<(PlayerDir, PlayerState)>,
-    )>,
+// Wciśnięcie w trakcie przejścia czeka w kolejce, a przytrzymany kierunek powtarza kroki
+fn player_input_system(
+    mut move_input: ResMut<MoveInput>,
+    mut move_events: EventWriter<MoveEvent>,
     input: Res<Input<KeyCode>>,
+    settings: Res<MovementSettings>,
+    time: Res<Time>,
+    query: Query<&GridTween, With<Player>>,
 ) {
-    for (mut transform, mut anim) in query.iter_mut() {
-        let mut dir = (0, 0);
-
-        if input.pressed(KeyCode::D) {
-            dir.0 += 1;
-        }
-        if input.pressed(KeyCode::W) {
-            dir.1 += 1;
-        }
-        if input.pressed(KeyCode::S) {
-            dir.1 -= 1;
+    for &(dir, keys) in DIRECTION_KEYS.iter() {
+        if input.any_just_pressed(keys) {
+            if move_input.buffered.len() < settings.buffer_size {
+                move_input.buffered.push_back(dir);
+            }
+            move_input.held = Some(dir);
+            move_input.held_for = 0.;
         }
-        if input.pressed(KeyCode::A) {
-            dir.0 -= 1;
+    }
+    let still_held = DIRECTION_KEYS
+        .iter()
+        .any(|&(dir, keys)| move_input.held == Some(dir) && input.any_pressed(keys));
+    if still_held {
+        move_input.held_for += time.delta_seconds();
+    } else {
+        move_input.held = None;
+    }
+
+    if query.iter().any(GridTween::is_moving) {
+        return;
+    }
+    if let Some(dir) = move_input.buffered.pop_front() {
+        move_events.send(MoveEvent(dir));
+    } else if let Some(dir) = move_input.held {
+        if move_input.held_for >= settings.repeat_delay {
+            move_events.send(MoveEvent(dir));
         }
+    }
+}
 
-        let mut state = anim.current_state;
+fn move_player_system(
+    mut move_events: EventReader<MoveEvent>,
+    mut player_query: Query<
+        (
+            &mut GridPos,
+            &mut AnimationStateMachine<(PlayerDir, PlayerState)>,
+        ),
+        With<Player>,
+    >,
+    mut crate_query: Query<&mut GridPos, (With<Crate>, Without<Player>)>,
+    mut level: ResMut<Level>,
+    mut history: ResMut<History>,
+    mut solved_events: EventWriter<LevelSolvedEvent>,
+) {
+    for &MoveEvent(dir) in move_events.iter() {
+        if level.is_solved() {
+            break;
+        }
+        for (mut grid_pos, mut anim) in player_query.iter_mut() {
+            let (facing_before, state) = anim.current_state;
+            anim.switch_state((dir, state));
 
-        match dir {
-            (_, 1) => {
-                state = (PlayerDir::Up, PlayerState::Walk);
-            }
-            (_, -1) => {
-                state = (PlayerDir::Down, PlayerState::Walk);
-            }
-            (1, 0) => {
-                state = (PlayerDir::Right, PlayerState::Walk);
+            let Some(step) = level.try_step(dir.offset()) else {
+                continue;
+            };
+            grid_pos.0 = step.to;
+            if let Some((from, to)) = step.pushed {
+                move_crate(crate_query.iter_mut(), from, to);
             }
-            (-1, 0) => {
-                state = (PlayerDir::Left, PlayerState::Walk);
-            }
-            _ => {
-                state = (state.0, PlayerState::Idle);
+            history.record(HistoryEntry {
+                step,
+                facing_before,
+                facing_after: dir,
+            });
+            if level.is_solved() {
+                info!("Level solved!");
+                solved_events.send(LevelSolvedEvent);
             }
         }
+    }
+}
 
-        anim.switch_state(state);
+// Chód trwa dokładnie tyle, co przejście między polami
+fn player_animation_system(
+    mut query: Query<
+        (
+            &GridTween,
+            &mut AnimationStateMachine<(PlayerDir, PlayerState)>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (tween, mut anim) in query.iter_mut() {
+        let facing = anim.current_state.0;
+        let state = if tween.is_moving() {
+            PlayerState::Walk
+        } else {
+            PlayerState::Idle
+        };
+        anim.switch_state((facing, state));
+    }
+}
 
-        transform.translation += Vec3::new(dir.0 as f32 * 5., dir.1 as f32 * 5., 0.0);
+fn reset_player_system(
+    mut started_events: EventReader<LevelStartedEvent>,
+    level: Res<Level>,
+    mut move_input: ResMut<MoveInput>,
+    mut query: Query<
+        (
+            &mut GridPos,
+            &mut Transform,
+            &mut AnimationStateMachine<(PlayerDir, PlayerState)>,
+        ),
+        With<Player>,
+    >,
+) {
+    if started_events.iter().last().is_none() {
+        return;
+    }
+    move_input.buffered.clear();
+    for (mut grid_pos, mut transform, mut anim) in query.iter_mut() {
+        grid_pos.0 = level.player;
+        snap_to_grid(&level, &grid_pos, &mut transform);
+        anim.switch_state((PlayerDir::Down, PlayerState::Idle));
     }
 }
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins)
-        .init_resource::<MainAtlas>()
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            watch_for_changes: true,
+            ..default()
+        }))
+        .add_plugin(EguiPlugin)
+        .add_plugin(ProfilesPlugin { game: "puzzle" })
+        .add_plugin(MovementPlugin)
         .add_startup_system(setup_camera)
-        .add_startup_system(setup_player.label("player"))
-        .add_startup_system(setup_assets.before("player"))
-        .add_system(player)
+        .add_state(GameState::Loading)
+        .add_plugin(AtlasPlugin)
+        .add_plugin(BoardPlugin)
+        .add_plugin(PacksPlugin)
+        .add_plugin(HistoryPlugin)
+        .add_plugin(SolverPlugin)
+        .add_plugin(EditorPlugin)
+        .add_plugin(DeadlockPlugin)
+        .add_plugin(ScorePlugin)
+        .add_event::<MoveEvent>()
+        .init_resource::<MoveInput>()
+        .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup_player))
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(player_input_system.after(tween_system))
+                .with_system(
+                    move_player_system
+                        .after(player_input_system)
+                        .before(start_tween_system),
+                ),
+        )
+        .add_system(
+            reset_player_system
+                .after(tween_system)
+                .before(start_tween_system),
+        )
+        .add_system(player_animation_system.after(start_tween_system))
         .add_system(advance_animation_system::<AnimationStateMachine<(PlayerDir, PlayerState)>>)
         .add_system(apply_animation_system::<AnimationStateMachine<(PlayerDir, PlayerState)>>)
         .run();
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::{
+    board::{grid_to_world, GridPos},
+    sokoban::Level,
+};
+
+pub struct MovementPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    Linear,
+    #[default]
+    SmoothStep,
+    EaseOutQuad,
+    EaseInOutCubic,
+}
+
+impl Easing {
+    // Postęp przejścia 0..1 przeliczony na drogę 0..1
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::SmoothStep => t * t * (3. - 2. * t),
+            Easing::EaseOutQuad => 1. - (1. - t) * (1. - t),
+            Easing::EaseInOutCubic if t < 0.5 => 4. * t * t * t,
+            Easing::EaseInOutCubic => 1. - (-2. * t + 2.).powi(3) / 2.,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MovementSettings {
+    pub step_seconds: f32,
+    pub easing: Easing,
+    // Po takim czasie przytrzymany kierunek zaczyna powtarzać kroki
+    pub repeat_delay: f32,
+    // Ile wciśnięć w trakcie przejścia czeka na wykonanie
+    pub buffer_size: usize,
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        Self {
+            step_seconds: 0.12,
+            easing: Easing::default(),
+            repeat_delay: 0.2,
+            buffer_size: 2,
+        }
+    }
+}
+
+// Przejście między polami; pozycja logiczna (GridPos) zmienia się od razu na początku kroku
+#[derive(Component, Default)]
+pub struct GridTween {
+    from: Vec3,
+    to: Vec3,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl GridTween {
+    pub fn is_moving(&self) -> bool {
+        self.elapsed < self.duration
+    }
+
+    fn progress(&self) -> f32 {
+        if self.duration > 0. {
+            self.elapsed / self.duration
+        } else {
+            1.
+        }
+    }
+}
+
+// Cofnięcie ruchu i restart nie animują przejścia; tween zatrzyma się przy następnej
+// zmianie GridPos, bo zacznie się i skończy w tym samym miejscu
+pub fn snap_to_grid(level: &Level, grid_pos: &GridPos, transform: &mut Transform) {
+    transform.translation = grid_to_world(level, grid_pos.0, transform.translation.z);
+}
+
+pub fn start_tween_system(
+    level: Res<Level>,
+    settings: Res<MovementSettings>,
+    mut query: Query<(&GridPos, &Transform, &mut GridTween), Changed<GridPos>>,
+) {
+    for (grid_pos, transform, mut tween) in query.iter_mut() {
+        let from = transform.translation;
+        let to = grid_to_world(&level, grid_pos.0, from.z);
+        let duration = if from == to {
+            0.
+        } else {
+            settings.step_seconds
+        };
+        *tween = GridTween {
+            from,
+            to,
+            elapsed: 0.,
+            duration,
+        };
+    }
+}
+
+pub fn tween_system(
+    time: Res<Time>,
+    settings: Res<MovementSettings>,
+    mut query: Query<(&mut GridTween, &mut Transform)>,
+) {
+    for (mut tween, mut transform) in query.iter_mut() {
+        if !tween.is_moving() {
+            continue;
+        }
+        tween.elapsed = (tween.elapsed + time.delta_seconds()).min(tween.duration);
+        let t = settings.easing.apply(tween.progress());
+        transform.translation = tween.from.lerp(tween.to, t);
+    }
+}
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementSettings>()
+            .add_system(tween_system)
+            .add_system(start_tween_system.after(tween_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_start_and_end_on_tiles() {
+        for easing in [
+            Easing::Linear,
+            Easing::SmoothStep,
+            Easing::EaseOutQuad,
+            Easing::EaseInOutCubic,
+        ] {
+            assert_eq!(easing.apply(0.), 0.);
+            assert_eq!(easing.apply(1.), 1.);
+            assert!((easing.apply(0.5) - 0.5).abs() <= 0.25);
+        }
+    }
+}
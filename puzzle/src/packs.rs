@@ -0,0 +1,267 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use profiles::{Profile, Profiles};
+
+use crate::{
+    board::{level_key, CurrentLevel},
+    editor::{custom_levels_path, CUSTOM_LEVELS},
+    score::{level_completed, record_board},
+    sokoban::Level,
+    xsb::LevelCollection,
+    GameState,
+};
+
+const LEVEL_SELECT_KEY: KeyCode = KeyCode::Tab;
+const NEXT_LEVEL_KEY: KeyCode = KeyCode::N;
+
+pub struct PacksPlugin;
+
+pub struct PackInfo {
+    pub title: &'static str,
+    pub path: &'static str,
+    // Ile poziomów poprzedniego pakietu trzeba ukończyć, żeby otworzyć ten
+    pub unlock_after: usize,
+}
+
+pub const PACKS: [PackInfo; 2] = [
+    PackInfo {
+        title: "Intro",
+        path: "levels/intro.xsb",
+        unlock_after: 0,
+    },
+    PackInfo {
+        title: "Storage",
+        path: "levels/storage.xsb",
+        unlock_after: 3,
+    },
+];
+
+// Uchwyty w kolejności PACKS
+#[derive(Resource, Default)]
+pub struct LevelPacks {
+    pub handles: Vec<Handle<LevelCollection>>,
+    // Poziomy zapisane w edytorze, jeśli jakieś są
+    pub custom: Option<Handle<LevelCollection>>,
+}
+
+// Pakiet otwiera się po ukończeniu wymaganej liczby poziomów w poprzednim, też otwartym
+fn pack_unlocked(packs: &[PackInfo], completed: &[usize], index: usize) -> bool {
+    index == 0
+        || (completed[index - 1] >= packs[index].unlock_after
+            && pack_unlocked(packs, completed, index - 1))
+}
+
+// Postęp wynika z liczników ukończonych poziomów zapisanych w profilu
+fn completed_levels(profile: &Profile, pack: &PackInfo, count: usize) -> usize {
+    (0..count)
+        .filter(|&index| level_completed(profile, &level_key(Path::new(pack.path), index)))
+        .count()
+}
+
+fn setup_packs(mut packs: ResMut<LevelPacks>, server: Res<AssetServer>) {
+    packs.handles = PACKS.iter().map(|pack| server.load(pack.path)).collect();
+    if custom_levels_path().exists() {
+        packs.custom = Some(server.load(CUSTOM_LEVELS));
+    }
+}
+
+fn draw_level_select(
+    mut egui_context: ResMut<EguiContext>,
+    packs: Res<LevelPacks>,
+    collections: Res<Assets<LevelCollection>>,
+    profiles: Res<Profiles>,
+    mut current: ResMut<CurrentLevel>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let profile = profiles.active();
+    let completed: Vec<usize> = PACKS
+        .iter()
+        .zip(packs.handles.iter())
+        .map(|(pack, handle)| {
+            let count = collections
+                .get(handle)
+                .map_or(0, |collection| collection.levels.len());
+            completed_levels(profile, pack, count)
+        })
+        .collect();
+    let mut selected = None;
+
+    egui::CentralPanel::default().show(egui_context.ctx_mut(), |ui| {
+        ui.heading("Select level");
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (pack_index, (pack, handle)) in PACKS.iter().zip(&packs.handles).enumerate() {
+                ui.separator();
+                let Some(collection) = collections.get(handle) else {
+                    ui.label(format!("{}: loading...", pack.title));
+                    continue;
+                };
+                let unlocked = pack_unlocked(&PACKS, &completed, pack_index);
+                ui.strong(format!(
+                    "{} - {}/{} solved",
+                    pack.title,
+                    completed[pack_index],
+                    collection.levels.len()
+                ));
+                if !unlocked {
+                    let previous = &PACKS[pack_index - 1];
+                    ui.label(format!(
+                        "Locked: solve {} levels in {} to open",
+                        pack.unlock_after, previous.title
+                    ));
+                }
+
+                egui::Grid::new(pack.path).striped(true).show(ui, |ui| {
+                    for (index, entry) in collection.levels.iter().enumerate() {
+                        let title = entry.title.as_deref().unwrap_or("Untitled");
+                        let button = egui::Button::new(format!("{}. {title}", index + 1));
+                        if ui.add_enabled(unlocked, button).clicked() {
+                            selected = Some((handle.clone(), index));
+                        }
+
+                        let key = level_key(Path::new(pack.path), index);
+                        let best = |stat: &str| profile.best(&record_board(&key, stat));
+                        match (best("moves"), best("pushes")) {
+                            (Some(moves), Some(pushes)) => {
+                                ui.label(format!("Best: {moves} moves, {pushes} pushes"))
+                            }
+                            _ if unlocked => ui.label("Not solved"),
+                            _ => ui.label("Locked"),
+                        };
+                        if let Some(rating) = best("stars") {
+                            ui.label(format!("{rating}/3 stars"));
+                        }
+                        ui.end_row();
+                    }
+                });
+            }
+
+            // Własne poziomy są zawsze otwarte i nie mają rekordów
+            let Some(handle) = &packs.custom else {
+                return;
+            };
+            let Some(collection) = collections.get(handle) else {
+                return;
+            };
+            ui.separator();
+            ui.strong("Custom");
+            for (index, entry) in collection.levels.iter().enumerate() {
+                let title = entry.title.as_deref().unwrap_or("Untitled");
+                if ui.button(format!("{}. {title}", index + 1)).clicked() {
+                    selected = Some((handle.clone(), index));
+                }
+            }
+        });
+    });
+
+    if let Some((collection, index)) = selected {
+        *current = CurrentLevel {
+            collection,
+            index,
+            playtest: None,
+        };
+        state.set(GameState::Playing).unwrap();
+    }
+}
+
+// Po przejściu ostatniego poziomu pakietu wracamy do wyboru poziomów
+fn next_level(
+    current: &mut CurrentLevel,
+    collections: &Assets<LevelCollection>,
+    state: &mut State<GameState>,
+) {
+    let count = collections
+        .get(&current.collection)
+        .map_or(0, |collection| collection.levels.len());
+    if current.playtest.is_none() && current.index + 1 < count {
+        current.index += 1;
+    } else {
+        state.set(GameState::LevelSelect).unwrap();
+    }
+}
+
+fn draw_pack_panel(
+    mut egui_context: ResMut<EguiContext>,
+    input: Res<Input<KeyCode>>,
+    packs: Res<LevelPacks>,
+    collections: Res<Assets<LevelCollection>>,
+    level: Res<Level>,
+    mut current: ResMut<CurrentLevel>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let pack = packs
+        .handles
+        .iter()
+        .position(|handle| *handle == current.collection)
+        .map(|index| &PACKS[index]);
+    let title = match (&current.playtest, pack) {
+        (Some(_), _) => "Playtest".to_string(),
+        (None, Some(pack)) => format!("{} {}", pack.title, current.index + 1),
+        (None, None) => format!("Level {}", current.index + 1),
+    };
+    let mut back = input.just_pressed(LEVEL_SELECT_KEY);
+    let mut next = level.is_solved() && input.just_pressed(NEXT_LEVEL_KEY);
+
+    egui::Window::new("Pack")
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10., 10.))
+        .title_bar(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(title);
+            back |= ui.button("Levels (Tab)").clicked();
+            if level.is_solved() {
+                next |= ui.button("Next level (N)").clicked();
+            }
+        });
+
+    if back {
+        state.set(GameState::LevelSelect).unwrap();
+    } else if next {
+        next_level(&mut current, &collections, &mut state);
+    }
+}
+
+impl Plugin for PacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelPacks>()
+            .add_startup_system(setup_packs)
+            .add_system_set(
+                SystemSet::on_update(GameState::LevelSelect).with_system(draw_level_select),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(draw_pack_panel));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_unlock_in_order() {
+        assert!(pack_unlocked(&PACKS, &[0, 0], 0));
+        assert!(!pack_unlocked(&PACKS, &[2, 0], 1));
+        assert!(pack_unlocked(&PACKS, &[3, 0], 1));
+
+        let chain = [
+            PackInfo {
+                title: "A",
+                path: "a.xsb",
+                unlock_after: 0,
+            },
+            PackInfo {
+                title: "B",
+                path: "b.xsb",
+                unlock_after: 2,
+            },
+            PackInfo {
+                title: "C",
+                path: "c.xsb",
+                unlock_after: 1,
+            },
+        ];
+        // Poziomy ukończone w zablokowanym pakiecie nie otwierają kolejnego
+        assert!(!pack_unlocked(&chain, &[1, 1, 0], 2));
+        assert!(pack_unlocked(&chain, &[2, 1, 0], 2));
+    }
+}
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use profiles::{Profile, Profiles, ScoreOrder};
+
+use crate::{
+    board::{CurrentLevel, LevelSolvedEvent, LevelStartedEvent},
+    deadlock::{dead_squares_system, DeadSquares},
+    history::History,
+    sokoban::Level,
+    solver::{AssistEvent, SolverJob, SolverOutcome},
+    xsb::LevelCollection,
+    GameState,
+};
+
+const GAME: &str = "puzzle";
+
+pub struct ScorePlugin;
+
+// Trzy gwiazdki za par, dwie za najwyżej połowę pchnięć ponad par, jedna za samo ukończenie
+pub fn stars(pushes: usize, par: u32) -> u8 {
+    let pushes = pushes as u32;
+    if pushes <= par {
+        3
+    } else if pushes * 2 <= par * 3 {
+        2
+    } else {
+        1
+    }
+}
+
+pub fn record_board(key: &str, stat: &str) -> String {
+    format!("{key}.{stat}")
+}
+
+// Licznik rośnie tylko po przejściu bez pomocy solvera, więc odtworzone rozwiązanie
+// nie otwiera kolejnych pakietów
+pub fn level_completed(profile: &Profile, key: &str) -> bool {
+    profile
+        .stats
+        .get(GAME)
+        .and_then(|stats| stats.counters.get(&record_board(key, "completed")))
+        .copied()
+        .unwrap_or(0)
+        > 0
+}
+
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[derive(Resource, Default)]
+struct LevelProgress {
+    key: Option<String>,
+    par: Option<u32>,
+    // Par poziomów bez linii "Par:" liczy solver w tle
+    par_job: Option<SolverJob>,
+    started_at: f64,
+    finished_at: Option<f64>,
+    // Po podpowiedzi albo odtworzeniu rozwiązania wynik się nie liczy
+    assisted: bool,
+    // Ponowienie ruchu po cofnięciu znów kończy poziom; zapisujemy tylko pierwsze przejście
+    recorded: bool,
+}
+
+impl LevelProgress {
+    fn elapsed(&self, now: f64) -> f64 {
+        self.finished_at.unwrap_or(now) - self.started_at
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_level_system(
+    mut started_events: EventReader<LevelStartedEvent>,
+    mut progress: ResMut<LevelProgress>,
+    current: Res<CurrentLevel>,
+    collections: Res<Assets<LevelCollection>>,
+    server: Res<AssetServer>,
+    level: Res<Level>,
+    dead: Res<DeadSquares>,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+) {
+    if started_events.iter().last().is_none() {
+        return;
+    }
+    // Edytor zaczyna poziom od nowa przy każdej zmianie - tam par nie ma sensu
+    let from_collection = current.playtest.is_none() && *state.current() == GameState::Playing;
+    let par = collections
+        .get(&current.collection)
+        .and_then(|collection| collection.levels.get(current.index))
+        .and_then(|entry| entry.par)
+        .filter(|_| from_collection);
+    *progress = LevelProgress {
+        key: current.record_key(&server),
+        par,
+        par_job: (from_collection && par.is_none()).then(|| SolverJob::new(&level, &dead)),
+        started_at: time.elapsed_seconds_f64(),
+        ..default()
+    };
+}
+
+fn assist_system(mut assist_events: EventReader<AssistEvent>, mut progress: ResMut<LevelProgress>) {
+    if assist_events.iter().last().is_some() {
+        progress.assisted = true;
+    }
+}
+
+fn par_system(mut progress: ResMut<LevelProgress>) {
+    let Some(outcome) = progress.par_job.as_mut().and_then(SolverJob::poll) else {
+        return;
+    };
+    progress.par_job = None;
+    if let SolverOutcome::Solved(pushes) = outcome {
+        progress.par = Some(pushes.len() as u32);
+    }
+}
+
+fn finish_level_system(
+    mut solved_events: EventReader<LevelSolvedEvent>,
+    mut progress: ResMut<LevelProgress>,
+    mut profiles: ResMut<Profiles>,
+    history: Res<History>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    if solved_events.iter().last().is_none() {
+        // Cofnięcie ruchu po ukończeniu wznawia zegar
+        if progress.finished_at.is_some() && !level.is_solved() {
+            progress.finished_at = None;
+        }
+        return;
+    }
+    let now = time.elapsed_seconds_f64();
+    progress.finished_at = Some(now);
+    if progress.assisted || progress.recorded {
+        return;
+    }
+    progress.recorded = true;
+    let Some(key) = &progress.key else {
+        return;
+    };
+
+    let profile = profiles.active_mut();
+    profile.count(GAME, "levels_solved", 1);
+    profile.count(GAME, &record_board(key, "completed"), 1);
+    let results = [
+        ("moves", history.moves() as i64),
+        ("pushes", history.pushes() as i64),
+        ("time_ms", (progress.elapsed(now) * 1000.) as i64),
+    ];
+    for (stat, score) in results {
+        profile.record_score(&record_board(key, stat), score, ScoreOrder::LowerIsBetter);
+    }
+    // Bez paru nie da się uczciwie ocenić przejścia
+    if let Some(par) = progress.par {
+        let rating = stars(history.pushes(), par) as i64;
+        profile.record_score(
+            &record_board(key, "stars"),
+            rating,
+            ScoreOrder::HigherIsBetter,
+        );
+    }
+    profiles.save_or_log();
+}
+
+fn draw_hud(
+    mut egui_context: ResMut<EguiContext>,
+    progress: Res<LevelProgress>,
+    history: Res<History>,
+    profiles: Res<Profiles>,
+    time: Res<Time>,
+) {
+    let par = match (progress.par, &progress.par_job) {
+        (Some(par), _) => format!("Par: {par} pushes"),
+        (None, Some(_)) => "Par: computing...".to_string(),
+        (None, None) => "Par: -".to_string(),
+    };
+    let profile = profiles.active();
+    let best = |stat: &str| {
+        let key = progress.key.as_ref()?;
+        profile.best(&record_board(key, stat))
+    };
+
+    egui::Window::new("Level")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(10., 10.))
+        .title_bar(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("Moves: {}", history.moves()));
+            ui.label(format!("Pushes: {}", history.pushes()));
+            let elapsed = progress.elapsed(time.elapsed_seconds_f64());
+            ui.label(format!("Time: {}", format_time(elapsed)));
+            ui.label(par);
+
+            if let (Some(moves), Some(pushes)) = (best("moves"), best("pushes")) {
+                ui.separator();
+                ui.label(format!("Best: {moves} moves, {pushes} pushes"));
+                if let Some(time_ms) = best("time_ms") {
+                    ui.label(format!(
+                        "Best time: {}",
+                        format_time(time_ms as f64 / 1000.)
+                    ));
+                }
+                if let Some(rating) = best("stars") {
+                    ui.label(format!("Best rating: {rating}/3 stars"));
+                }
+            }
+
+            if progress.finished_at.is_some() {
+                ui.separator();
+                match progress.par {
+                    _ if progress.assisted => {
+                        ui.strong("Solved with help - not recorded");
+                    }
+                    Some(par) => {
+                        let rating = stars(history.pushes(), par);
+                        ui.strong(format!("Solved! {rating}/3 stars"));
+                    }
+                    None => {
+                        ui.strong("Solved!");
+                    }
+                }
+            }
+        });
+}
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelProgress>()
+            .add_system(start_level_system.after(dead_squares_system))
+            .add_system(par_system.after(start_level_system))
+            .add_system(assist_system.after(start_level_system))
+            .add_system(finish_level_system.after(assist_system))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(draw_hud));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::board::level_key;
+
+    #[test]
+    fn rates_pushes_against_par() {
+        assert_eq!(stars(10, 10), 3);
+        assert_eq!(stars(15, 10), 2);
+        assert_eq!(stars(16, 10), 1);
+        assert_eq!(stars(0, 0), 3);
+    }
+
+    #[test]
+    fn only_completion_counter_marks_level_done() {
+        let key = &level_key(Path::new("levels/intro.xsb"), 0);
+        let mut profile = Profile::new("test");
+        profile.record_score(&record_board(key, "moves"), 12, ScoreOrder::LowerIsBetter);
+        assert!(!level_completed(&profile, key));
+        profile.count(GAME, &record_board(key, "completed"), 1);
+        assert!(level_completed(&profile, key));
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Tile {
+    // Pole poza poziomem
+    #[default]
+    Empty,
+    Wall,
+    Floor,
+    Goal,
+}
+
+impl Tile {
+    pub fn walkable(self) -> bool {
+        matches!(self, Tile::Floor | Tile::Goal)
+    }
+}
+
+pub const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+// Wynik jednego kroku gracza; pushed to pozycje skrzyni przed i po pchnięciu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub from: IVec2,
+    pub to: IVec2,
+    pub pushed: Option<(IVec2, IVec2)>,
+}
+
+// Współrzędne siatki: x w prawo, y (wiersz) w dół, tak jak w zapisie poziomu
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Level {
+    pub width: i32,
+    pub height: i32,
+    tiles: Vec<Tile>,
+    pub boxes: HashSet<IVec2>,
+    pub player: IVec2,
+}
+
+impl Level {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![Tile::Empty; (width * height) as usize],
+            boxes: HashSet::new(),
+            player: IVec2::ZERO,
+        }
+    }
+
+    // Wiersze w notacji Sokobana: # ściana, @ gracz, $ skrzynia, . cel, * skrzynia na celu, + gracz na celu
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut level = Self::new(width as i32, rows.len() as i32);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                let pos = IVec2::new(x as i32, y as i32);
+                let tile = match symbol {
+                    '#' => Tile::Wall,
+                    '.' | '*' | '+' => Tile::Goal,
+                    '@' | '$' | ' ' | '-' | '_' => Tile::Floor,
+                    _ => Tile::Empty,
+                };
+                level.set_tile(pos, tile);
+                if matches!(symbol, '$' | '*') {
+                    level.boxes.insert(pos);
+                }
+                if matches!(symbol, '@' | '+') {
+                    level.player = pos;
+                }
+            }
+        }
+        level.clear_outside();
+        level
+    }
+
+    // Podłoga, do której gracz nie dojdzie bez przechodzenia przez ściany, leży poza poziomem
+    fn clear_outside(&mut self) {
+        let mut inside = HashSet::from([self.player]);
+        let mut queue = VecDeque::from([self.player]);
+        while let Some(pos) = queue.pop_front() {
+            for dir in DIRECTIONS {
+                let next = pos + dir;
+                if self.tile(next).walkable() && inside.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        for pos in self.positions().collect::<Vec<_>>() {
+            if self.tile(pos) == Tile::Floor && !inside.contains(&pos) && !self.has_box(pos) {
+                self.set_tile(pos, Tile::Empty);
+            }
+        }
+    }
+
+    // Zmiana rozmiaru zachowuje lewy górny róg; skrzynie poza nowym obszarem znikają
+    pub fn resize(&mut self, width: i32, height: i32) {
+        let mut resized = Self::new(width, height);
+        for pos in resized.positions().collect::<Vec<_>>() {
+            resized.set_tile(pos, self.tile(pos));
+        }
+        resized.boxes = self
+            .boxes
+            .iter()
+            .copied()
+            .filter(|&pos| resized.contains(pos))
+            .collect();
+        resized.player = self.player.min(IVec2::new(width - 1, height - 1));
+        *self = resized;
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        pos.x >= 0 && pos.y >= 0 && pos.x < self.width && pos.y < self.height
+    }
+
+    pub fn tile(&self, pos: IVec2) -> Tile {
+        if !self.contains(pos) {
+            return Tile::Empty;
+        }
+        self.tiles[(pos.y * self.width + pos.x) as usize]
+    }
+
+    pub fn set_tile(&mut self, pos: IVec2, tile: Tile) {
+        if self.contains(pos) {
+            let index = (pos.y * self.width + pos.x) as usize;
+            self.tiles[index] = tile;
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| IVec2::new(x, y)))
+    }
+
+    pub fn has_box(&self, pos: IVec2) -> bool {
+        self.boxes.contains(&pos)
+    }
+
+    pub fn is_free(&self, pos: IVec2) -> bool {
+        self.tile(pos).walkable() && !self.has_box(pos)
+    }
+
+    // Skrzynię można tylko pchać i tylko jedną naraz
+    pub fn try_step(&mut self, dir: IVec2) -> Option<Step> {
+        let from = self.player;
+        let to = from + dir;
+        if !self.tile(to).walkable() {
+            return None;
+        }
+
+        let pushed = if self.has_box(to) {
+            let box_to = to + dir;
+            if !self.is_free(box_to) {
+                return None;
+            }
+            self.boxes.remove(&to);
+            self.boxes.insert(box_to);
+            Some((to, box_to))
+        } else {
+            None
+        };
+
+        self.player = to;
+        Some(Step { from, to, pushed })
+    }
+
+    pub fn undo_step(&mut self, step: &Step) {
+        if let Some((box_from, box_to)) = step.pushed {
+            self.boxes.remove(&box_to);
+            self.boxes.insert(box_from);
+        }
+        self.player = step.from;
+    }
+
+    pub fn redo_step(&mut self, step: &Step) {
+        if let Some((box_from, box_to)) = step.pushed {
+            self.boxes.remove(&box_from);
+            self.boxes.insert(box_to);
+        }
+        self.player = step.to;
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.boxes.is_empty() && self.boxes.iter().all(|&pos| self.tile(pos) == Tile::Goal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RIGHT: IVec2 = IVec2::new(1, 0);
+
+    #[test]
+    fn pushes_single_box_onto_goal() {
+        let mut level = Level::from_rows(&["#####", "#@$.#", "#####"]);
+        let step = level.try_step(RIGHT).unwrap();
+        assert_eq!(step.pushed, Some((IVec2::new(2, 1), IVec2::new(3, 1))));
+        assert!(level.is_solved());
+    }
+
+    #[test]
+    fn cannot_push_into_wall_or_second_box() {
+        let mut level = Level::from_rows(&["#####", "#@$#", "#####"]);
+        assert_eq!(level.try_step(RIGHT), None);
+
+        let mut level = Level::from_rows(&["######", "#@$$.#", "######"]);
+        assert_eq!(level.try_step(RIGHT), None);
+        assert_eq!(level.player, IVec2::new(1, 1));
+    }
+
+    #[test]
+    fn undo_restores_box_and_player() {
+        let mut level = Level::from_rows(&["######", "#@$ .#", "######"]);
+        let step = level.try_step(RIGHT).unwrap();
+        level.undo_step(&step);
+        assert_eq!(level.player, IVec2::new(1, 1));
+        assert!(level.has_box(IVec2::new(2, 1)));
+
+        level.redo_step(&step);
+        assert_eq!(level.player, IVec2::new(2, 1));
+        assert!(level.has_box(IVec2::new(3, 1)));
+    }
+
+    #[test]
+    fn walking_away_does_not_pull() {
+        let mut level = Level::from_rows(&["#####", "# @$#", "#####"]);
+        assert_eq!(level.tile(IVec2::new(1, 1)), Tile::Floor);
+        let step = level.try_step(IVec2::new(-1, 0)).unwrap();
+        assert_eq!(step.pushed, None);
+        assert!(level.has_box(IVec2::new(3, 1)));
+        assert!(!level.is_solved());
+    }
+}
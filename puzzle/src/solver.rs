@@ -0,0 +1,720 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+
+use crate::{
+    atlas::{Region, Sprites},
+    board::{grid_to_world, tile_sprite, Crate, GridPos, LevelStartedEvent},
+    deadlock::DeadSquares,
+    sokoban::{Level, Tile, DIRECTIONS},
+    GameState, MoveEvent, PlayerDir,
+};
+
+const HINT_KEY: KeyCode = KeyCode::H;
+const SOLVE_KEY: KeyCode = KeyCode::P;
+const CANCEL_KEY: KeyCode = KeyCode::Escape;
+const PLAYBACK_STEP_SECS: f32 = 0.15;
+const HINT_COLOR: Color = Color::rgb(1., 0.85, 0.3);
+const HINT_Z: f32 = 4.;
+
+// Co tyle rozwiniętych stanów sprawdzamy limit czasu i przerwanie
+const BUDGET_CHECK_INTERVAL: usize = 1024;
+
+pub struct SolverPlugin;
+
+// Jedno pchnięcie: skrzynia z pola box_from przesuwa się o dir
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Push {
+    pub box_from: IVec2,
+    pub dir: IVec2,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SolverBudget {
+    pub time_limit: Duration,
+    // Przybliżony rozmiar tablicy stanów w bajtach
+    pub memory_limit: usize,
+}
+
+impl Default for SolverBudget {
+    fn default() -> Self {
+        Self {
+            time_limit: Duration::from_secs(10),
+            memory_limit: 256 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolverOutcome {
+    // Najkrótsze rozwiązanie liczone w pchnięciach
+    Solved(Vec<Push>),
+    Unsolvable,
+    OutOfBudget,
+    Cancelled,
+}
+
+// Stan to same skrzynie i obszar gracza, reprezentowany przez najmniejsze osiągalne pole
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    boxes: Vec<u16>,
+    player: u16,
+}
+
+struct Node {
+    state: State,
+    parent: usize,
+    push: Option<(u16, u8)>,
+    cost: u32,
+}
+
+// Niezmienna część poziomu z polami numerowanymi wierszami
+struct SearchMap {
+    width: i32,
+    height: i32,
+    walkable: Vec<bool>,
+    goals: Vec<bool>,
+    // Odległości z DeadSquares; u32::MAX oznacza martwe pole
+    goal_distance: Vec<u32>,
+}
+
+impl SearchMap {
+    fn new(level: &Level, dead: &DeadSquares) -> Self {
+        let mut map = Self {
+            width: level.width,
+            height: level.height,
+            walkable: Vec::new(),
+            goals: Vec::new(),
+            goal_distance: Vec::new(),
+        };
+        for pos in level.positions() {
+            map.walkable.push(level.tile(pos).walkable());
+            map.goals.push(level.tile(pos) == Tile::Goal);
+            map.goal_distance
+                .push(dead.goal_distance(pos).unwrap_or(u32::MAX));
+        }
+        map
+    }
+
+    fn cell(&self, pos: IVec2) -> usize {
+        (pos.y * self.width + pos.x) as usize
+    }
+
+    fn pos(&self, cell: usize) -> IVec2 {
+        IVec2::new(cell as i32 % self.width, cell as i32 / self.width)
+    }
+
+    fn neighbor(&self, cell: usize, offset: IVec2) -> Option<usize> {
+        let pos = self.pos(cell) + offset;
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < self.width && pos.y < self.height;
+        inside.then(|| self.cell(pos))
+    }
+
+    fn is_dead(&self, cell: usize) -> bool {
+        self.goal_distance[cell] == u32::MAX
+    }
+
+    // Pola, na które gracz dojdzie bez ruszania skrzyń
+    fn reachable(&self, player: usize, boxes: &[u16]) -> Vec<bool> {
+        let mut seen = vec![false; self.walkable.len()];
+        seen[player] = true;
+        let mut stack = vec![player];
+        while let Some(cell) = stack.pop() {
+            for dir in DIRECTIONS {
+                let Some(next) = self.neighbor(cell, dir) else {
+                    continue;
+                };
+                if self.walkable[next] && !seen[next] && !has_box(boxes, next) {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    fn normalize(&self, player: usize, boxes: &[u16]) -> u16 {
+        let reachable = self.reachable(player, boxes);
+        reachable.iter().position(|&seen| seen).unwrap_or(player) as u16
+    }
+
+    fn heuristic(&self, boxes: &[u16]) -> u32 {
+        boxes
+            .iter()
+            .map(|&cell| self.goal_distance[cell as usize])
+            .sum()
+    }
+
+    fn is_blocked(&self, cell: Option<usize>, boxes: &[u16]) -> bool {
+        cell.map_or(true, |cell| !self.walkable[cell] || has_box(boxes, cell))
+    }
+
+    // Kwadrat 2x2 ze ścian i skrzyń już się nie rozpadnie; przegrana, jeśli jest w nim skrzynia poza celem
+    fn frozen_square(&self, cell: usize, boxes: &[u16]) -> bool {
+        [IVec2::new(-1, -1), IVec2::NEG_Y, IVec2::NEG_X, IVec2::ZERO]
+            .into_iter()
+            .any(|corner| {
+                let square = [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE]
+                    .map(|offset| self.neighbor(cell, corner + offset));
+                square.iter().all(|&cell| self.is_blocked(cell, boxes))
+                    && square
+                        .iter()
+                        .flatten()
+                        .any(|&cell| has_box(boxes, cell) && !self.goals[cell])
+            })
+    }
+
+    fn pushes(&self, nodes: &[Node], mut index: usize) -> Vec<Push> {
+        let mut pushes = Vec::new();
+        while let Some((cell, dir)) = nodes[index].push {
+            pushes.push(Push {
+                box_from: self.pos(cell as usize),
+                dir: DIRECTIONS[dir as usize],
+            });
+            index = nodes[index].parent;
+        }
+        pushes.reverse();
+        pushes
+    }
+}
+
+fn has_box(boxes: &[u16], cell: usize) -> bool {
+    boxes.binary_search(&(cell as u16)).is_ok()
+}
+
+// A* po pchnięciach; heurystyka (suma odległości skrzyń od celów) nie przeszacowuje,
+// więc znalezione rozwiązanie ma najmniej pchnięć
+pub fn solve(
+    level: &Level,
+    dead: &DeadSquares,
+    budget: SolverBudget,
+    cancel: &AtomicBool,
+) -> SolverOutcome {
+    let started = Instant::now();
+    // Pola z innego poziomu, np. sprzed zmiany w edytorze, liczymy od nowa
+    let fresh;
+    let dead = if dead.fits(level) {
+        dead
+    } else {
+        fresh = DeadSquares::new(level);
+        &fresh
+    };
+    let map = SearchMap::new(level, dead);
+    let mut boxes: Vec<u16> = level
+        .boxes
+        .iter()
+        .map(|&pos| map.cell(pos) as u16)
+        .collect();
+    boxes.sort_unstable();
+    if boxes.iter().any(|&cell| map.is_dead(cell as usize)) {
+        return SolverOutcome::Unsolvable;
+    }
+
+    // Stan trzymamy dwa razy: w węźle i jako klucz tablicy transpozycji
+    let node_bytes = mem::size_of::<Node>()
+        + mem::size_of::<(State, usize)>()
+        + 2 * boxes.len() * mem::size_of::<u16>();
+    let max_nodes = (budget.memory_limit / node_bytes).max(1);
+
+    let root = State {
+        player: map.normalize(map.cell(level.player), &boxes),
+        boxes,
+    };
+    let mut open = BinaryHeap::from([Reverse((map.heuristic(&root.boxes), 0, 0))]);
+    let mut table = HashMap::from([(root.clone(), 0)]);
+    let mut nodes = vec![Node {
+        state: root,
+        parent: 0,
+        push: None,
+        cost: 0,
+    }];
+
+    let mut expanded = 0;
+    while let Some(Reverse((_, cost, index))) = open.pop() {
+        if cost > nodes[index].cost {
+            continue;
+        }
+        expanded += 1;
+        if expanded % BUDGET_CHECK_INTERVAL == 0 {
+            if cancel.load(Ordering::Relaxed) {
+                return SolverOutcome::Cancelled;
+            }
+            if started.elapsed() > budget.time_limit {
+                return SolverOutcome::OutOfBudget;
+            }
+        }
+
+        let state = nodes[index].state.clone();
+        if state.boxes.iter().all(|&cell| map.goals[cell as usize]) {
+            return SolverOutcome::Solved(map.pushes(&nodes, index));
+        }
+
+        let reachable = map.reachable(state.player as usize, &state.boxes);
+        for (box_index, &box_cell) in state.boxes.iter().enumerate() {
+            let box_cell = box_cell as usize;
+            for (dir_index, dir) in DIRECTIONS.into_iter().enumerate() {
+                let (Some(stand), Some(target)) =
+                    (map.neighbor(box_cell, -dir), map.neighbor(box_cell, dir))
+                else {
+                    continue;
+                };
+                if !reachable[stand] || map.is_dead(target) || has_box(&state.boxes, target) {
+                    continue;
+                }
+                let mut boxes = state.boxes.clone();
+                boxes[box_index] = target as u16;
+                boxes.sort_unstable();
+                if map.frozen_square(target, &boxes) {
+                    continue;
+                }
+
+                let child = State {
+                    player: map.normalize(box_cell, &boxes),
+                    boxes,
+                };
+                let child_cost = cost + 1;
+                let child_index = match table.get(&child) {
+                    Some(&known) if nodes[known].cost <= child_cost => continue,
+                    Some(&known) => known,
+                    None => {
+                        if nodes.len() >= max_nodes {
+                            return SolverOutcome::OutOfBudget;
+                        }
+                        table.insert(child.clone(), nodes.len());
+                        nodes.push(Node {
+                            state: child,
+                            parent: index,
+                            push: None,
+                            cost: child_cost,
+                        });
+                        nodes.len() - 1
+                    }
+                };
+                let node = &mut nodes[child_index];
+                node.parent = index;
+                node.push = Some((box_cell as u16, dir_index as u8));
+                node.cost = child_cost;
+                let estimate = child_cost + map.heuristic(&node.state.boxes);
+                open.push(Reverse((estimate, child_cost, child_index)));
+            }
+        }
+    }
+    SolverOutcome::Unsolvable
+}
+
+// Najkrótsza droga gracza na pole, bez pchania skrzyń
+fn walk_path(level: &Level, target: IVec2) -> Option<Vec<IVec2>> {
+    let mut came_from = HashMap::from([(level.player, level.player)]);
+    let mut queue = VecDeque::from([level.player]);
+    while let Some(pos) = queue.pop_front() {
+        if pos == target {
+            let mut path = Vec::new();
+            let mut current = pos;
+            while current != level.player {
+                let previous = came_from[&current];
+                path.push(current - previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for dir in DIRECTIONS {
+            let next = pos + dir;
+            if level.is_free(next) && !came_from.contains_key(&next) {
+                came_from.insert(next, pos);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Rozpisuje pchnięcia na pojedyncze kroki gracza
+pub fn solution_steps(level: &Level, pushes: &[Push]) -> Option<Vec<IVec2>> {
+    let mut level = level.clone();
+    let mut steps = Vec::new();
+    for push in pushes {
+        for dir in walk_path(&level, push.box_from - push.dir)? {
+            level.try_step(dir)?;
+            steps.push(dir);
+        }
+        level.try_step(push.dir)?.pushed?;
+        steps.push(push.dir);
+    }
+    Some(steps)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SolverGoal {
+    #[default]
+    Hint,
+    Solve,
+    // Sprawdzenie poziomu z edytora
+    Verify,
+}
+
+// Zadanie w tle razem z pozycją, od której liczy; porzucone zadanie dostaje sygnał przerwania
+pub struct SolverJob {
+    pub start: Level,
+    cancel: Arc<AtomicBool>,
+    task: Task<SolverOutcome>,
+}
+
+impl SolverJob {
+    pub fn new(level: &Level, dead: &DeadSquares) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let task = {
+            let level = level.clone();
+            let dead = dead.clone();
+            let cancel = cancel.clone();
+            AsyncComputeTaskPool::get()
+                .spawn(async move { solve(&level, &dead, SolverBudget::default(), &cancel) })
+        };
+        Self {
+            start: level.clone(),
+            cancel,
+            task,
+        }
+    }
+
+    // Wynik, jeśli zadanie już się skończyło
+    pub fn poll(&mut self) -> Option<SolverOutcome> {
+        future::block_on(future::poll_once(&mut self.task))
+    }
+}
+
+impl Drop for SolverJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// Zadanie na prośbę gracza albo edytora; nowa prośba przerywa poprzednią
+#[derive(Resource, Default)]
+pub struct Solver {
+    goal: SolverGoal,
+    job: Option<SolverJob>,
+}
+
+impl Solver {
+    pub fn request(&mut self, goal: SolverGoal, level: &Level, dead: &DeadSquares) {
+        self.goal = goal;
+        self.job = Some(SolverJob::new(level, dead));
+    }
+
+    pub fn cancel(&mut self) -> bool {
+        self.job.take().is_some()
+    }
+}
+
+#[derive(Resource)]
+struct Playback {
+    steps: VecDeque<PlayerDir>,
+    timer: Timer,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            steps: VecDeque::new(),
+            timer: Timer::from_seconds(PLAYBACK_STEP_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component)]
+struct HintMarker;
+
+// Podpowiedź albo odtwarzanie rozwiązania - takie przejście nie trafia do rekordów
+pub struct AssistEvent;
+
+// Poziom z edytora, dla którego solver znalazł rozwiązanie
+pub struct LevelVerifiedEvent(pub Level);
+
+fn solver_input_system(
+    input: Res<Input<KeyCode>>,
+    level: Res<Level>,
+    dead: Res<DeadSquares>,
+    mut solver: ResMut<Solver>,
+    mut playback: ResMut<Playback>,
+) {
+    if input.just_pressed(CANCEL_KEY) {
+        if solver.cancel() {
+            info!("Solver cancelled");
+        }
+        playback.steps.clear();
+        return;
+    }
+
+    let goal = if input.just_pressed(HINT_KEY) {
+        SolverGoal::Hint
+    } else if input.just_pressed(SOLVE_KEY) {
+        SolverGoal::Solve
+    } else {
+        return;
+    };
+    if level.is_solved() {
+        return;
+    }
+    info!("Solving...");
+    playback.steps.clear();
+    solver.request(goal, &level, &dead);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn solver_result_system(
+    mut commands: Commands,
+    mut solver: ResMut<Solver>,
+    mut playback: ResMut<Playback>,
+    mut assist_events: EventWriter<AssistEvent>,
+    mut verified_events: EventWriter<LevelVerifiedEvent>,
+    level: Res<Level>,
+    sprites: Res<Sprites>,
+    mut crate_query: Query<(&GridPos, &mut TextureAtlasSprite), With<Crate>>,
+) {
+    let Some(outcome) = solver.job.as_mut().and_then(SolverJob::poll) else {
+        return;
+    };
+    let Some(job) = solver.job.take() else {
+        return;
+    };
+    // Gracz ruszył się albo poziom zmieniono w trakcie liczenia - wynik dotyczy innej pozycji
+    if job.start != *level {
+        return;
+    }
+
+    let pushes = match outcome {
+        SolverOutcome::Solved(pushes) => pushes,
+        SolverOutcome::Unsolvable if solver.goal == SolverGoal::Verify => {
+            warn!("Level has no solution");
+            return;
+        }
+        SolverOutcome::Unsolvable => {
+            info!("No solution from here, undo some moves");
+            return;
+        }
+        SolverOutcome::OutOfBudget => {
+            info!("Solver ran out of time or memory");
+            return;
+        }
+        SolverOutcome::Cancelled => return,
+    };
+
+    match solver.goal {
+        SolverGoal::Hint => {
+            let Some(&push) = pushes.first() else {
+                return;
+            };
+            for (grid_pos, mut sprite) in crate_query.iter_mut() {
+                if grid_pos.0 == push.box_from {
+                    sprite.color = HINT_COLOR;
+                }
+            }
+            let mut marker = tile_sprite(
+                &sprites,
+                Region::Goal,
+                grid_to_world(&level, push.box_from + push.dir, HINT_Z),
+            );
+            marker.sprite.color = HINT_COLOR;
+            commands.spawn((HintMarker, marker));
+            assist_events.send(AssistEvent);
+        }
+        SolverGoal::Solve => {
+            let steps = solution_steps(&level, &pushes).unwrap_or_default();
+            info!("Solution: {} pushes, {} moves", pushes.len(), steps.len());
+            playback.steps = steps
+                .into_iter()
+                .filter_map(PlayerDir::from_offset)
+                .collect();
+            playback.timer.reset();
+            assist_events.send(AssistEvent);
+        }
+        SolverGoal::Verify => {
+            info!("Level can be solved in {} pushes", pushes.len());
+            verified_events.send(LevelVerifiedEvent(job.start));
+        }
+    }
+}
+
+// Podpowiedź dotyczy pozycji, w której o nią poproszono
+fn clear_hint_system(
+    mut commands: Commands,
+    level: Res<Level>,
+    marker_query: Query<Entity, With<HintMarker>>,
+    mut crate_query: Query<&mut TextureAtlasSprite, With<Crate>>,
+) {
+    if !level.is_changed() || marker_query.is_empty() {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for mut sprite in crate_query.iter_mut() {
+        if sprite.color == HINT_COLOR {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
+
+// Dowolny klawisz przerywa odtwarzanie, żeby gracz mógł przejąć sterowanie
+fn playback_system(
+    time: Res<Time>,
+    input: Res<Input<KeyCode>>,
+    mut playback: ResMut<Playback>,
+    mut move_events: EventWriter<MoveEvent>,
+) {
+    if playback.steps.is_empty() {
+        return;
+    }
+    if input.get_just_pressed().any(|&key| key != SOLVE_KEY) {
+        playback.steps.clear();
+        info!("Solution playback stopped");
+        return;
+    }
+    if playback.timer.tick(time.delta()).just_finished() {
+        if let Some(dir) = playback.steps.pop_front() {
+            move_events.send(MoveEvent(dir));
+        }
+    }
+}
+
+fn reset_solver_system(
+    mut started_events: EventReader<LevelStartedEvent>,
+    mut solver: ResMut<Solver>,
+    mut playback: ResMut<Playback>,
+) {
+    if started_events.iter().last().is_some() {
+        solver.cancel();
+        playback.steps.clear();
+    }
+}
+
+impl Plugin for SolverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AssistEvent>()
+            .add_event::<LevelVerifiedEvent>()
+            .init_resource::<Solver>()
+            .init_resource::<Playback>()
+            .add_system(reset_solver_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(solver_input_system.after(reset_solver_system))
+                    .with_system(playback_system.after(solver_input_system)),
+            )
+            .add_system(solver_result_system.after(solver_input_system))
+            .add_system(clear_hint_system.before(solver_result_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+    use crate::xsb::parse_collection;
+
+    fn solve_rows(rows: &[&str]) -> SolverOutcome {
+        let level = Level::from_rows(rows);
+        let dead = DeadSquares::new(&level);
+        solve(
+            &level,
+            &dead,
+            SolverBudget::default(),
+            &AtomicBool::new(false),
+        )
+    }
+
+    #[test]
+    fn finds_fewest_pushes() {
+        let outcome = solve_rows(&["#######", "#@ $ .#", "#######"]);
+        let SolverOutcome::Solved(pushes) = outcome else {
+            panic!("{outcome:?}");
+        };
+        assert_eq!(pushes.len(), 2);
+        assert_eq!(pushes[0].box_from, IVec2::new(3, 1));
+    }
+
+    #[test]
+    fn detects_dead_positions() {
+        let outcome = solve_rows(&["#####", "#@ $#", "#.  #", "#####"]);
+        assert_eq!(outcome, SolverOutcome::Unsolvable);
+    }
+
+    #[test]
+    fn ignores_dead_squares_of_another_level() {
+        let level = Level::from_rows(&["#######", "#@ $ .#", "#######"]);
+        let dead = DeadSquares::new(&Level::from_rows(&["#####", "#@$.#", "#####"]));
+        let outcome = solve(
+            &level,
+            &dead,
+            SolverBudget::default(),
+            &AtomicBool::new(false),
+        );
+        assert!(matches!(outcome, SolverOutcome::Solved(_)), "{outcome:?}");
+    }
+
+    #[test]
+    fn stops_when_out_of_memory() {
+        let level = Level::from_rows(&["#######", "#@ $ .#", "#######"]);
+        let budget = SolverBudget {
+            memory_limit: 0,
+            ..default()
+        };
+        let dead = DeadSquares::new(&level);
+        let outcome = solve(&level, &dead, budget, &AtomicBool::new(false));
+        assert_eq!(outcome, SolverOutcome::OutOfBudget);
+    }
+
+    #[test]
+    fn shipped_levels_are_solvable() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/levels");
+        for file in fs::read_dir(dir).unwrap() {
+            let path = file.unwrap().path();
+            if path.extension().map_or(true, |ext| ext != "xsb") {
+                continue;
+            }
+            let text = fs::read_to_string(&path).unwrap();
+            let collection =
+                parse_collection(&text).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+            for (index, entry) in collection.levels.iter().enumerate() {
+                let dead = DeadSquares::new(&entry.level);
+                let outcome = solve(
+                    &entry.level,
+                    &dead,
+                    SolverBudget::default(),
+                    &AtomicBool::new(false),
+                );
+                let SolverOutcome::Solved(pushes) = outcome else {
+                    panic!("{} level {}: {outcome:?}", path.display(), index + 1);
+                };
+                // Par poniżej optimum odebrałby graczom trzy gwiazdki
+                if let Some(par) = entry.par {
+                    assert!(
+                        pushes.len() as u32 <= par,
+                        "{} level {}",
+                        path.display(),
+                        index + 1
+                    );
+                }
+                let mut level = entry.level.clone();
+                for dir in solution_steps(&entry.level, &pushes).unwrap() {
+                    level.try_step(dir).unwrap();
+                }
+                assert!(level.is_solved(), "{} level {}", path.display(), index + 1);
+            }
+        }
+    }
+}
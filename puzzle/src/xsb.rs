@@ -0,0 +1,382 @@
+use std::{collections::HashSet, error::Error, fmt};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+
+use crate::sokoban::{Level, Tile, DIRECTIONS};
+
+const FLOOR_CHARS: [char; 3] = [' ', '-', '_'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XsbError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl XsbError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for XsbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for XsbError {}
+
+#[derive(Clone, Debug)]
+pub struct LevelEntry {
+    pub title: Option<String>,
+    // Docelowa liczba pchnięć z linii "Par: ..."
+    pub par: Option<u32>,
+    pub level: Level,
+}
+
+#[derive(TypeUuid, Clone, Debug, Default)]
+#[uuid = "c18904fa-2c3d-49c0-8a54-b07aab5e651f"]
+pub struct LevelCollection {
+    pub levels: Vec<LevelEntry>,
+}
+
+#[derive(Default)]
+pub struct XsbLoader;
+
+impl AssetLoader for XsbLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            let collection = parse_collection(text)?;
+            load_context.set_default_asset(LoadedAsset::new(collection));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xsb"]
+    }
+}
+
+// Wiersze planszy zaczynają się (po spacjach) od ściany
+fn is_board_line(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+struct Draft<'a> {
+    title: Option<String>,
+    explicit_title: bool,
+    par: Option<u32>,
+    rows: Vec<(usize, &'a str)>,
+    // Po pustej linii poziom nie przyjmuje już tytułu, po innej linii tekstu - wierszy
+    open_for_rows: bool,
+    open_for_meta: bool,
+}
+
+impl<'a> Draft<'a> {
+    fn new(title: Option<(String, bool)>, par: Option<u32>) -> Self {
+        let (title, explicit_title) = match title {
+            Some((title, explicit)) => (Some(title), explicit),
+            None => (None, false),
+        };
+        Self {
+            title,
+            explicit_title,
+            par,
+            rows: Vec::new(),
+            open_for_rows: true,
+            open_for_meta: true,
+        }
+    }
+
+    fn offer_title(&mut self, title: &str, explicit: bool) {
+        if self.title.is_none() || (explicit && !self.explicit_title) {
+            self.title = Some(title.to_string());
+            self.explicit_title = explicit;
+        }
+    }
+}
+
+// Plik może zawierać wiele poziomów rozdzielonych pustymi liniami. Tytuł poziomu to linia
+// "Title: ..." albo komentarz "; ..." / zwykły tekst tuż przy planszy, przed nią lub po niej
+pub fn parse_collection(text: &str) -> Result<LevelCollection, XsbError> {
+    let mut levels = Vec::new();
+    let mut current: Option<Draft> = None;
+    let mut pending_title: Option<(String, bool)> = None;
+    let mut pending_par = None;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw_line.trim_end_matches('\r');
+
+        if line.trim().is_empty() {
+            if let Some(draft) = current.as_mut() {
+                draft.open_for_rows = false;
+                draft.open_for_meta = false;
+            }
+            continue;
+        }
+
+        if is_board_line(line) {
+            if current.as_ref().map_or(true, |draft| !draft.open_for_rows) {
+                if let Some(draft) = current.take() {
+                    levels.push(finish_level(draft)?);
+                }
+                current = Some(Draft::new(pending_title.take(), pending_par.take()));
+            }
+            current.as_mut().unwrap().rows.push((line_no, line));
+            continue;
+        }
+
+        let (title, explicit) = if let Some(comment) = line.trim_start().strip_prefix(';') {
+            (comment.trim(), false)
+        } else if let Some((key, value)) = line.split_once(':') {
+            if key.trim().eq_ignore_ascii_case("par") {
+                let column = line.len() - value.trim_start().len() + 1;
+                let par = value.trim().parse().map_err(|_| {
+                    XsbError::new(line_no, column, format!("invalid par '{}'", value.trim()))
+                })?;
+                match current.as_mut() {
+                    Some(draft) if draft.open_for_meta => {
+                        draft.open_for_rows = false;
+                        draft.par = Some(par);
+                    }
+                    _ => pending_par = Some(par),
+                }
+                continue;
+            }
+            if !key.trim().eq_ignore_ascii_case("title") {
+                // Author, Comment i inne metadane pomijamy
+                if let Some(draft) = current.as_mut() {
+                    draft.open_for_rows = false;
+                }
+                continue;
+            }
+            (value.trim(), true)
+        } else {
+            (line.trim(), false)
+        };
+
+        match current.as_mut() {
+            Some(draft) if draft.open_for_meta => {
+                draft.open_for_rows = false;
+                if !title.is_empty() {
+                    draft.offer_title(title, explicit);
+                }
+            }
+            _ if title.is_empty() => {}
+            _ => {
+                if pending_title
+                    .as_ref()
+                    .map_or(true, |(_, pending_explicit)| explicit || !pending_explicit)
+                {
+                    pending_title = Some((title.to_string(), explicit));
+                }
+            }
+        }
+    }
+
+    if let Some(draft) = current.take() {
+        levels.push(finish_level(draft)?);
+    }
+    if levels.is_empty() {
+        return Err(XsbError::new(1, 1, "file contains no levels"));
+    }
+    Ok(LevelCollection { levels })
+}
+
+fn finish_level(draft: Draft) -> Result<LevelEntry, XsbError> {
+    let (first_line, _) = draft.rows[0];
+    let grid: Vec<Vec<char>> = draft
+        .rows
+        .iter()
+        .map(|(_, row)| row.chars().collect())
+        .collect();
+
+    let mut player = None;
+    let (mut boxes, mut goals) = (0, 0);
+    for (y, row) in grid.iter().enumerate() {
+        let line_no = draft.rows[y].0;
+        for (x, &symbol) in row.iter().enumerate() {
+            match symbol {
+                '#' | '.' | '$' | '*' | '@' | '+' => {}
+                _ if FLOOR_CHARS.contains(&symbol) => {}
+                _ => {
+                    return Err(XsbError::new(
+                        line_no,
+                        x + 1,
+                        format!("unexpected character '{symbol}'"),
+                    ))
+                }
+            }
+            if matches!(symbol, '$' | '*') {
+                boxes += 1;
+            }
+            if matches!(symbol, '.' | '*' | '+') {
+                goals += 1;
+            }
+            if matches!(symbol, '@' | '+') {
+                if player.is_some() {
+                    return Err(XsbError::new(line_no, x + 1, "level has a second player"));
+                }
+                player = Some(IVec2::new(x as i32, y as i32));
+            }
+        }
+    }
+
+    let Some(player) = player else {
+        return Err(XsbError::new(first_line, 1, "level has no player"));
+    };
+    if boxes == 0 {
+        return Err(XsbError::new(first_line, 1, "level has no boxes"));
+    }
+    if boxes != goals {
+        return Err(XsbError::new(
+            first_line,
+            1,
+            format!("level has {boxes} boxes but {goals} goals"),
+        ));
+    }
+
+    // Gracz nie może wyjść poza planszę - szukamy dziury w ścianach
+    let cell = |pos: IVec2| -> Option<char> {
+        if pos.y < 0 || pos.x < 0 {
+            return None;
+        }
+        grid.get(pos.y as usize)?.get(pos.x as usize).copied()
+    };
+    let mut visited = HashSet::from([player]);
+    let mut stack = vec![player];
+    while let Some(pos) = stack.pop() {
+        for dir in DIRECTIONS {
+            let next = pos + dir;
+            match cell(next) {
+                Some('#') => {}
+                Some(_) => {
+                    if visited.insert(next) {
+                        stack.push(next);
+                    }
+                }
+                None => {
+                    let line_no = draft.rows[pos.y as usize].0;
+                    return Err(XsbError::new(
+                        line_no,
+                        pos.x as usize + 1,
+                        "level is not closed by walls here",
+                    ));
+                }
+            }
+        }
+    }
+
+    let rows: Vec<&str> = draft.rows.iter().map(|(_, row)| *row).collect();
+    Ok(LevelEntry {
+        title: draft.title,
+        par: draft.par,
+        level: Level::from_rows(&rows),
+    })
+}
+
+// Zapis poziomu w notacji Sokobana, przycięty do pól należących do poziomu
+pub fn write_level(level: &Level) -> String {
+    let used: Vec<IVec2> = level
+        .positions()
+        .filter(|&pos| level.tile(pos) != Tile::Empty)
+        .collect();
+    let min = used.iter().copied().reduce(IVec2::min).unwrap_or_default();
+    let max = used.iter().copied().reduce(IVec2::max).unwrap_or_default();
+
+    let mut text = String::new();
+    for y in min.y..=max.y {
+        let row: String = (min.x..=max.x)
+            .map(|x| {
+                let pos = IVec2::new(x, y);
+                match (level.tile(pos), level.has_box(pos), level.player == pos) {
+                    (Tile::Wall, ..) => '#',
+                    (Tile::Goal, true, _) => '*',
+                    (Tile::Goal, _, true) => '+',
+                    (Tile::Goal, ..) => '.',
+                    (_, true, _) => '$',
+                    (_, _, true) => '@',
+                    _ => ' ',
+                }
+            })
+            .collect();
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_titles_and_comments() {
+        let text = "; Pack comment\n\n; 1\n\n#####\n#@$.#\n#####\nTitle: First\n\n; Second\n####\n#@*#\n#$.#\n####\n";
+        let collection = parse_collection(text).unwrap();
+        let titles: Vec<_> = collection
+            .levels
+            .iter()
+            .map(|entry| entry.title.as_deref())
+            .collect();
+        assert_eq!(titles, [Some("First"), Some("Second")]);
+        assert_eq!(collection.levels[0].par, None);
+        assert_eq!(collection.levels[1].level.boxes.len(), 2);
+    }
+
+    #[test]
+    fn reads_par_before_or_after_board() {
+        let text = "#####\n#@$.#\n#####\nPar: 1\n\nPar: 3\n####\n#@*#\n#$.#\n####\n";
+        let collection = parse_collection(text).unwrap();
+        let pars: Vec<_> = collection.levels.iter().map(|entry| entry.par).collect();
+        assert_eq!(pars, [Some(1), Some(3)]);
+
+        let err = parse_collection("#####\n#@$.#\n#####\nPar: many").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 6));
+    }
+
+    #[test]
+    fn reports_position_of_bad_character() {
+        let err = parse_collection("#####\n#@$x#\n#####").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+    }
+
+    #[test]
+    fn rejects_open_levels_and_unbalanced_goals() {
+        let err = parse_collection("#####\n#@$. \n#####").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+
+        let err = parse_collection("\n######\n#@$$.#\n######").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("2 boxes but 1 goals"));
+    }
+
+    #[test]
+    fn written_level_parses_back() {
+        let rows = ["  ####", "###  #", "#.$@ #", "#  *##", "#####"];
+        let level = Level::from_rows(&rows);
+        let text = write_level(&level);
+        assert_eq!(text.lines().collect::<Vec<_>>(), rows);
+        assert_eq!(parse_collection(&text).unwrap().levels[0].level, level);
+    }
+}
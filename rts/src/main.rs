@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use bevy_mod_picking::*;
 use leafwing_input_manager::prelude::*;
+use profiles::ProfilesPlugin;
 
 use camera::CameraPlugin;
 use ui::UiPlugin;
@@ -23,5 +24,6 @@ fn main() {
         .add_plugin(UiPlugin)
         .add_plugin(BoardPlugin)
         .add_plugin(UnitPlugin)
+        .add_plugin(ProfilesPlugin { game: "rts" })
         .run();
 }